@@ -29,13 +29,12 @@ use std::mem::size_of;
 
 /// Example that parses the layout and prints it. Only runs on Linux.
 #[start]
-fn start(_argc: isize, argv: *const *const u8) -> isize {
-    let buf = unsafe {
+fn start(argc: isize, argv: *const *const u8) -> isize {
+    let parsed = unsafe {
         // the stack layout begins `size_of::<usize>()` bytes before argv (at the address of argc)
         let ptr_layout_begin = argv.cast::<u8>().sub(size_of::<usize>());
-        core::slice::from_raw_parts(ptr_layout_begin.cast(), 10000)
+        InitialLinuxLibcStackLayout::from_raw_parts(ptr_layout_begin, 10000, Some(argc as usize))
     };
-    let parsed = InitialLinuxLibcStackLayout::from(buf);
 
     println!("There are {} arguments.", parsed.argc());
     println!(
@@ -60,13 +59,9 @@ fn start(_argc: isize, argv: *const *const u8) -> isize {
     }
 
     println!("  aux");
-    // ptr iter is safe for other address spaces; the other only because here user_addr == write_addr
-    for aux in unsafe { parsed.aux_var_iter() } {
-        if aux.key().value_in_data_area() {
-            println!("    {:?} => @ {:?}", aux.key(), aux);
-        } else {
-            println!("    {:?} => {:?}", aux.key(), aux);
-        }
+    // safe only because here user_addr == write_addr
+    for line in unsafe { parsed.auxv_table() }.lines() {
+        println!("    {}", line);
     }
 
     0
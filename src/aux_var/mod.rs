@@ -22,17 +22,24 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 
+#[cfg(feature = "serde")]
+mod serde_impl;
 mod serialized;
 mod typ;
 
 use crate::cstr_util::{c_str_len_ptr, c_str_null_terminated};
+use crate::{DataAreaValueError, TypeMismatchError};
+use alloc::string::String;
 use core::cmp::Ordering;
 use core::fmt::Debug;
+#[cfg(feature = "serde")]
+pub use serde_impl::AuxVarOwned;
 pub(crate) use serialized::*;
 pub use typ::*;
 
 bitflags::bitflags! {
     /// Flags for the auxiliary vector. See <https://elixir.bootlin.com/linux/v5.15.5/source/include/uapi/linux/binfmts.h#L23>.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct AuxVarFlags: usize {
         /// Opposite of [`Self::PRESERVE_ARGV0`].
         const NOT_PRESERVE_ARGV0 = 0;
@@ -41,9 +48,40 @@ bitflags::bitflags! {
     }
 }
 
+bitflags::bitflags! {
+    /// Bits of [`AuxVarType::HwCap2`] on x86 and x86_64. See
+    /// <https://elixir.bootlin.com/linux/v6.1/source/arch/x86/include/uapi/asm/hwcap2.h>.
+    pub struct HwCap2X86: usize {
+        /// The `MONITOR`/`MWAIT` instructions are enabled in ring 3.
+        const RING3MWAIT = 1 << 0;
+        /// The kernel allows the `FSGSBASE` instructions (`RDFSBASE` etc.) in user space.
+        const FSGSBASE = 1 << 1;
+    }
+}
+
+impl AuxVarFlags {
+    /// Flags with a non-zero value and their names in the Linux source code.
+    const NAMED_FLAGS: [(&'static str, Self); 1] = [("PRESERVE_ARGV0", Self::PRESERVE_ARGV0)];
+
+    /// Iterates over the names of all set flags, e.g., `PRESERVE_ARGV0`. Flags without a value
+    /// such as [`Self::NOT_PRESERVE_ARGV0`] and unknown bits are not listed.
+    pub fn iter_names(&self) -> impl Iterator<Item = &'static str> {
+        let flags = *self;
+        Self::NAMED_FLAGS
+            .into_iter()
+            .filter(move |(_, flag)| flags.contains(*flag))
+            .map(|(name, _)| name)
+    }
+}
+
 /// High-level version of the serialized form of an auxiliary vector entry. It is used to construct
 /// the auxiliary vector in [`crate::InitialLinuxLibcStackLayoutBuilder`] and returned when
 /// a data structure is parsed with [`crate::InitialLinuxLibcStackLayout`].
+///
+/// [`PartialEq`], [`Eq`], [`PartialOrd`], and [`Ord`] only take the key ([`AuxVarType`]) into
+/// account but not the payload. Hence, `AuxVar::Uid(0) == AuxVar::Uid(1)`. This keeps equality
+/// consistent with the ordering, which is required to use [`AuxVar`] in ordered collections,
+/// such as a `BTreeSet`. In the auxiliary vector, each key is unique anyway.
 #[derive(Debug)]
 pub enum AuxVar<'a> {
     /// Entry with payload for type [`AuxVarType::Null`].
@@ -94,6 +132,14 @@ pub enum AuxVar<'a> {
     Random([u8; 16]),
     /// Entry with payload for type [`AuxVarType::HwCap2`].
     HwCap2(usize),
+    /// Entry with payload for type [`AuxVarType::RseqFeatureSize`].
+    RseqFeatureSize(usize),
+    /// Entry with payload for type [`AuxVarType::RseqAlign`].
+    RseqAlign(usize),
+    /// Entry with payload for type [`AuxVarType::HwCap3`].
+    HwCap3(usize),
+    /// Entry with payload for type [`AuxVarType::HwCap4`].
+    HwCap4(usize),
     /// Entry with payload for type [`AuxVarType::ExecFn`].
     ExecFn(&'a str),
     /// Entry with payload for type [`AuxVarType::Sysinfo`].
@@ -120,6 +166,26 @@ pub enum AuxVar<'a> {
     MinSigStkSz(usize),
 }
 
+/// Payload of an [`AuxVar`], normalized into the possible shapes of a value. See
+/// [`AuxVar::value`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AuxValue<'a> {
+    /// Basic value/integer. See [`AuxVar::value_integer`].
+    Int(usize),
+    /// See [`AuxVar::value_boolean`].
+    Bool(bool),
+    /// See [`AuxVar::value_flags`].
+    Flags(AuxVarFlags),
+    /// Pointer to memory outside of the data structure. See [`AuxVar::value_ptr`].
+    Ptr(*const u8),
+    /// C-string from the aux data area. See [`AuxVar::value_payload_cstr`].
+    Str(&'a str),
+    /// Random bytes from the aux data area. See [`AuxVar::random`].
+    Random([u8; 16]),
+    /// No value, i.e. [`AuxVar::Null`].
+    None,
+}
+
 impl<'a> AuxVar<'a> {
     /// Creates the high-level type [`AuxVar`] from the serialized version of an
     /// AT variable/aux vec entry.
@@ -128,7 +194,12 @@ impl<'a> AuxVar<'a> {
     /// This function creates undefined behaviour or might even crash, if the AT value
     /// reference data in the aux vec data area, where the pointer is either invalid or
     /// if the C-strings is not null-terminated.
-    pub(crate) unsafe fn from_serialized(serialized: &AuxVarSerialized) -> Self {
+    ///
+    /// # Errors
+    /// Returns an error, if a C-string is not valid UTF-8.
+    pub(crate) unsafe fn from_serialized(
+        serialized: &AuxVarSerialized,
+    ) -> Result<Self, TypeMismatchError> {
         if serialized.key().value_in_data_area() {
             let data_ptr = serialized.val() as *const u8;
             let len = serialized
@@ -137,59 +208,100 @@ impl<'a> AuxVar<'a> {
                 .unwrap_or_else(|| c_str_len_ptr(data_ptr));
             let slice = core::slice::from_raw_parts(data_ptr, len);
             if serialized.key().value_is_cstr() {
-                let cstr = core::str::from_utf8(slice).expect(
-                    "must be valid c string. Either invalid memory or not null-terminated!",
-                );
-                match serialized.key() {
+                let cstr =
+                    core::str::from_utf8(slice).map_err(|_| TypeMismatchError(serialized.key()))?;
+                Ok(match serialized.key() {
                     AuxVarType::Platform => Self::Platform(cstr),
                     AuxVarType::BasePlatform => Self::BasePlatform(cstr),
                     AuxVarType::ExecFn => Self::ExecFn(cstr),
                     _ => panic!("invalid variant"),
-                }
+                })
             } else {
                 match serialized.key() {
                     AuxVarType::Random => {
                         let mut random_bytes = [0; 16];
                         // memcpy into new, fixed length slice
                         random_bytes.copy_from_slice(slice);
-                        Self::Random(random_bytes)
+                        Ok(Self::Random(random_bytes))
                     }
                     _ => panic!("invalid variant"),
                 }
             }
         } else {
-            match serialized.key() {
-                AuxVarType::Null => Self::Null,
-                AuxVarType::Ignore => Self::Ignore(serialized.val()),
-                AuxVarType::ExecFd => Self::ExecFd(serialized.val()),
-                AuxVarType::Phdr => Self::Phdr(serialized.val() as _),
-                AuxVarType::Phent => Self::Phent(serialized.val()),
-                AuxVarType::Phnum => Self::Phnum(serialized.val()),
-                AuxVarType::Pagesz => Self::Pagesz(serialized.val()),
-                AuxVarType::Base => Self::Base(serialized.val() as _),
-                AuxVarType::Flags => Self::Flags(AuxVarFlags::from_bits(serialized.val()).unwrap()),
-                AuxVarType::Entry => Self::Entry(serialized.val() as _),
-                AuxVarType::NotElf => Self::NotElf(serialized.val() != 0),
-                AuxVarType::Uid => Self::Uid(serialized.val()),
-                AuxVarType::EUid => Self::EUid(serialized.val()),
-                AuxVarType::Gid => Self::Gid(serialized.val()),
-                AuxVarType::EGid => Self::EGid(serialized.val()),
-                AuxVarType::HwCap => Self::HwCap(serialized.val()),
-                AuxVarType::Clktck => Self::Clktck(serialized.val()),
-                AuxVarType::Secure => Self::Secure(serialized.val() != 0),
-                AuxVarType::HwCap2 => Self::HwCap2(serialized.val()),
-                AuxVarType::Sysinfo => Self::Sysinfo(serialized.val() as _),
-                AuxVarType::SysinfoEhdr => Self::SysinfoEhdr(serialized.val() as _),
-                AuxVarType::L1iCacheSize => Self::L1iCacheSize(serialized.val()),
-                AuxVarType::L1iCacheGeometry => Self::L1iCacheGeometry(serialized.val()),
-                AuxVarType::L1dCacheSize => Self::L1dCacheSize(serialized.val()),
-                AuxVarType::L1dCacheGeometry => Self::L1dCacheGeometry(serialized.val()),
-                AuxVarType::L2CacheSize => Self::L2CacheSize(serialized.val()),
-                AuxVarType::L2CacheGeometry => Self::L2CacheGeometry(serialized.val()),
-                AuxVarType::L3CacheSize => Self::L3CacheSize(serialized.val()),
-                AuxVarType::L3CacheGeometry => Self::L3CacheGeometry(serialized.val()),
-                AuxVarType::MinSigStkSz => Self::MinSigStkSz(serialized.val()),
-                _ => panic!("invalid variant"),
+            Ok(Self::from_immediate(serialized.key(), serialized.val()).expect("invalid variant"))
+        }
+    }
+
+    /// Creates the high-level type [`AuxVar`] for a type, whose value doesn't live in the aux
+    /// vector data area, from its raw value. Returns `None` for types with referenced data
+    /// (see [`AuxVarType::value_in_data_area`]). As nothing is dereferenced, this is safe.
+    pub(crate) const fn from_immediate(key: AuxVarType, val: usize) -> Option<AuxVar<'static>> {
+        let var = match key {
+            AuxVarType::Null => AuxVar::Null,
+            AuxVarType::Ignore => AuxVar::Ignore(val),
+            AuxVarType::ExecFd => AuxVar::ExecFd(val),
+            AuxVarType::Phdr => AuxVar::Phdr(val as _),
+            AuxVarType::Phent => AuxVar::Phent(val),
+            AuxVarType::Phnum => AuxVar::Phnum(val),
+            AuxVarType::Pagesz => AuxVar::Pagesz(val),
+            AuxVarType::Base => AuxVar::Base(val as _),
+            AuxVarType::Flags => AuxVar::flags_from_raw(val),
+            AuxVarType::Entry => AuxVar::Entry(val as _),
+            AuxVarType::NotElf => AuxVar::NotElf(val != 0),
+            AuxVarType::Uid => AuxVar::Uid(val),
+            AuxVarType::EUid => AuxVar::EUid(val),
+            AuxVarType::Gid => AuxVar::Gid(val),
+            AuxVarType::EGid => AuxVar::EGid(val),
+            AuxVarType::HwCap => AuxVar::HwCap(val),
+            AuxVarType::Clktck => AuxVar::Clktck(val),
+            AuxVarType::Secure => AuxVar::Secure(val != 0),
+            AuxVarType::HwCap2 => AuxVar::HwCap2(val),
+            AuxVarType::RseqFeatureSize => AuxVar::RseqFeatureSize(val),
+            AuxVarType::RseqAlign => AuxVar::RseqAlign(val),
+            AuxVarType::HwCap3 => AuxVar::HwCap3(val),
+            AuxVarType::HwCap4 => AuxVar::HwCap4(val),
+            AuxVarType::Sysinfo => AuxVar::Sysinfo(val as _),
+            AuxVarType::SysinfoEhdr => AuxVar::SysinfoEhdr(val as _),
+            AuxVarType::L1iCacheSize => AuxVar::L1iCacheSize(val),
+            AuxVarType::L1iCacheGeometry => AuxVar::L1iCacheGeometry(val),
+            AuxVarType::L1dCacheSize => AuxVar::L1dCacheSize(val),
+            AuxVarType::L1dCacheGeometry => AuxVar::L1dCacheGeometry(val),
+            AuxVarType::L2CacheSize => AuxVar::L2CacheSize(val),
+            AuxVarType::L2CacheGeometry => AuxVar::L2CacheGeometry(val),
+            AuxVarType::L3CacheSize => AuxVar::L3CacheSize(val),
+            AuxVarType::L3CacheGeometry => AuxVar::L3CacheGeometry(val),
+            AuxVarType::MinSigStkSz => AuxVar::MinSigStkSz(val),
+            _ => return None,
+        };
+        Some(var)
+    }
+
+    /// Creates the high-level type [`AuxVar`] for a type, whose value lives in the aux vector
+    /// data area (see [`AuxVarType::value_in_data_area`]), from the referenced bytes. C-strings
+    /// end at the first null byte, if there is one, and [`AuxVarType::Random`] requires exactly
+    /// 16 bytes.
+    pub fn from_referenced(typ: AuxVarType, bytes: &'a [u8]) -> Result<Self, TypeMismatchError> {
+        Self::from_data_area_bytes(typ, bytes).ok_or(TypeMismatchError(typ))
+    }
+
+    /// Creates the high-level type [`AuxVar`] for a type, whose value lives in the aux vector
+    /// data area (see [`AuxVarType::value_in_data_area`]), from the referenced bytes. C-strings
+    /// end at the first null byte, if there is one. Returns `None`, if the type is not a data
+    /// area type, if a C-string is not valid UTF-8, or if the length doesn't match.
+    pub(crate) fn from_data_area_bytes(key: AuxVarType, bytes: &'a [u8]) -> Option<Self> {
+        if key.value_is_cstr() {
+            let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+            let cstr = core::str::from_utf8(&bytes[..len]).ok()?;
+            match key {
+                AuxVarType::Platform => Some(Self::Platform(cstr)),
+                AuxVarType::BasePlatform => Some(Self::BasePlatform(cstr)),
+                AuxVarType::ExecFn => Some(Self::ExecFn(cstr)),
+                _ => None,
+            }
+        } else {
+            match key {
+                AuxVarType::Random => bytes.try_into().ok().map(Self::Random),
+                _ => None,
             }
         }
     }
@@ -219,6 +331,10 @@ impl<'a> AuxVar<'a> {
             AuxVar::BasePlatform(_) => AuxVarType::BasePlatform,
             AuxVar::Random(_) => AuxVarType::Random,
             AuxVar::HwCap2(_) => AuxVarType::HwCap2,
+            AuxVar::RseqFeatureSize(_) => AuxVarType::RseqFeatureSize,
+            AuxVar::RseqAlign(_) => AuxVarType::RseqAlign,
+            AuxVar::HwCap3(_) => AuxVarType::HwCap3,
+            AuxVar::HwCap4(_) => AuxVarType::HwCap4,
             AuxVar::ExecFn(_) => AuxVarType::ExecFn,
             AuxVar::Sysinfo(_) => AuxVarType::Sysinfo,
             AuxVar::SysinfoEhdr(_) => AuxVarType::SysinfoEhdr,
@@ -237,6 +353,10 @@ impl<'a> AuxVar<'a> {
     /// Transforms any inner value into it's corresponding usize value.
     /// This is similar to the data that is serialized in the data structure on the stack,
     /// i.e. the value of the auxiliary vector entry.
+    ///
+    /// For variants that reference data in the aux data area, this is the address of the
+    /// payload in the current address space, but not the value that is serialized. Use
+    /// [`Self::try_value_raw`] to handle them explicitly.
     pub fn value_raw(&self) -> usize {
         match self {
             AuxVar::Null => 0,
@@ -273,6 +393,10 @@ impl<'a> AuxVar<'a> {
             AuxVar::BasePlatform(val) => val.as_ptr() as _,
             AuxVar::Random(val) => val.as_ptr() as _,
             AuxVar::HwCap2(val) => *val,
+            AuxVar::RseqFeatureSize(val) => *val,
+            AuxVar::RseqAlign(val) => *val,
+            AuxVar::HwCap3(val) => *val,
+            AuxVar::HwCap4(val) => *val,
             AuxVar::ExecFn(val) => val.as_ptr() as _,
             AuxVar::Sysinfo(val) => *val as _,
             AuxVar::SysinfoEhdr(val) => *val as _,
@@ -288,6 +412,37 @@ impl<'a> AuxVar<'a> {
         }
     }
 
+    /// Like [`Self::value_raw`], but fails for variants that reference data in the aux data
+    /// area, such as [`AuxVar::Platform`], because they have no standalone integer value.
+    pub fn try_value_raw(&self) -> Result<usize, DataAreaValueError> {
+        if self.key().value_in_data_area() {
+            Err(DataAreaValueError(self.key()))
+        } else {
+            Ok(self.value_raw())
+        }
+    }
+
+    /// Returns the payload normalized into one of the shapes of [`AuxValue`]. This is a single
+    /// point to exhaustively match on the values of all variants.
+    pub const fn value(&self) -> AuxValue<'a> {
+        match self {
+            Self::Flags(flags) => AuxValue::Flags(*flags),
+            Self::NotElf(val) | Self::Secure(val) => AuxValue::Bool(*val),
+            Self::Phdr(val)
+            | Self::Base(val)
+            | Self::Entry(val)
+            | Self::Sysinfo(val)
+            | Self::SysinfoEhdr(val) => AuxValue::Ptr(*val),
+            Self::Platform(val) | Self::BasePlatform(val) | Self::ExecFn(val) => AuxValue::Str(val),
+            Self::Random(bytes) => AuxValue::Random(*bytes),
+            // all remaining variants except for `Null` are integers
+            _ => match self.value_integer() {
+                Some(val) => AuxValue::Int(val),
+                None => AuxValue::None,
+            },
+        }
+    }
+
     /// Returns a value, if the corresponding auxiliary vector entry corresponds to a basic
     /// value/integer, and not a pointer, flags, or a boolean.
     pub const fn value_integer(&self) -> Option<usize> {
@@ -304,6 +459,10 @@ impl<'a> AuxVar<'a> {
             AuxVar::HwCap(val) => Some(*val),
             AuxVar::Clktck(val) => Some(*val),
             AuxVar::HwCap2(val) => Some(*val),
+            AuxVar::RseqFeatureSize(val) => Some(*val),
+            AuxVar::RseqAlign(val) => Some(*val),
+            AuxVar::HwCap3(val) => Some(*val),
+            AuxVar::HwCap4(val) => Some(*val),
             AuxVar::L1iCacheSize(val) => Some(*val),
             AuxVar::L1iCacheGeometry(val) => Some(*val),
             AuxVar::L1dCacheSize(val) => Some(*val),
@@ -325,6 +484,22 @@ impl<'a> AuxVar<'a> {
         }
     }
 
+    /// Returns the raw bits, if the corresponding auxiliary vector entry is of type
+    /// [`AuxVarType::Flags`]. Counterpart of [`Self::flags_from_raw`].
+    pub const fn raw_flags(&self) -> Option<usize> {
+        match self {
+            AuxVar::Flags(flags) => Some(flags.bits()),
+            _ => None,
+        }
+    }
+
+    /// Creates an entry of type [`AuxVarType::Flags`] from the raw bits of the ABI. Bits
+    /// without a corresponding flag in [`AuxVarFlags`] are retained.
+    pub const fn flags_from_raw(bits: usize) -> AuxVar<'static> {
+        // Safety: AuxVarFlags is only a wrapper around the bits; unknown bits are fine.
+        AuxVar::Flags(unsafe { AuxVarFlags::from_bits_unchecked(bits) })
+    }
+
     /// Returns a value, if the corresponding auxiliary vector entry corresponds to a
     /// boolean, and not a pointer, flags, or a basic value/integer.
     pub const fn value_boolean(&self) -> Option<bool> {
@@ -350,6 +525,95 @@ impl<'a> AuxVar<'a> {
         }
     }
 
+    /// Like [`Self::value_ptr`], but returns the address as `usize`.
+    pub fn as_addr(&self) -> Option<usize> {
+        self.value_ptr().map(|ptr| ptr as usize)
+    }
+
+    /// Creates an entry of type [`AuxVarType::Phdr`] from an address.
+    pub const fn phdr(addr: usize) -> Self {
+        Self::Phdr(addr as *const u8)
+    }
+
+    /// Creates an entry of type [`AuxVarType::Base`] from an address.
+    pub const fn base(addr: usize) -> Self {
+        Self::Base(addr as *const u8)
+    }
+
+    /// Creates an entry of type [`AuxVarType::Entry`] from an address.
+    pub const fn entry(addr: usize) -> Self {
+        Self::Entry(addr as *const u8)
+    }
+
+    /// Creates an entry of type [`AuxVarType::Sysinfo`] from an address.
+    pub const fn sysinfo(addr: usize) -> Self {
+        Self::Sysinfo(addr as *const u8)
+    }
+
+    /// Creates an entry of type [`AuxVarType::SysinfoEhdr`] from an address.
+    pub const fn sysinfo_ehdr(addr: usize) -> Self {
+        Self::SysinfoEhdr(addr as *const u8)
+    }
+
+    /// Decodes the bits of an entry of type [`AuxVarType::HwCap2`] as used on x86 and x86_64.
+    /// Unknown bits are retained. On other architectures, the bits have a different meaning.
+    pub const fn hwcap2_x86(&self) -> Option<HwCap2X86> {
+        match self {
+            // Safety: HwCap2X86 is only a wrapper around the bits; unknown bits are fine.
+            Self::HwCap2(bits) => Some(unsafe { HwCap2X86::from_bits_unchecked(*bits) }),
+            _ => None,
+        }
+    }
+
+    /// Returns the random bytes, if this is an entry of type [`AuxVarType::Random`]. In
+    /// contrast to [`Self::value_payload_bytes`], the size is known at compile time.
+    pub const fn random(&self) -> Option<&[u8; 16]> {
+        match self {
+            AuxVar::Random(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Returns the pointer, if this is an entry of type [`AuxVarType::Phdr`].
+    pub const fn as_phdr(&self) -> Option<*const u8> {
+        match self {
+            AuxVar::Phdr(val) => Some(*val),
+            _ => None,
+        }
+    }
+
+    /// Returns the pointer, if this is an entry of type [`AuxVarType::Base`].
+    pub const fn as_base(&self) -> Option<*const u8> {
+        match self {
+            AuxVar::Base(val) => Some(*val),
+            _ => None,
+        }
+    }
+
+    /// Returns the pointer, if this is an entry of type [`AuxVarType::Entry`].
+    pub const fn as_entry(&self) -> Option<*const u8> {
+        match self {
+            AuxVar::Entry(val) => Some(*val),
+            _ => None,
+        }
+    }
+
+    /// Returns the pointer, if this is an entry of type [`AuxVarType::Sysinfo`].
+    pub const fn as_sysinfo(&self) -> Option<*const u8> {
+        match self {
+            AuxVar::Sysinfo(val) => Some(*val),
+            _ => None,
+        }
+    }
+
+    /// Returns the pointer, if this is an entry of type [`AuxVarType::SysinfoEhdr`].
+    pub const fn as_sysinfo_ehdr(&self) -> Option<*const u8> {
+        match self {
+            AuxVar::SysinfoEhdr(val) => Some(*val),
+            _ => None,
+        }
+    }
+
     /// Returns a value, if the corresponding auxiliary vector entry references data in the
     /// auxiliary vector data area of the data structure.
     /// This returns only something for [`AuxVarType::Random`].
@@ -378,6 +642,60 @@ impl<'a> AuxVar<'a> {
         }
     }
 
+    /// Returns the string payload of [`AuxVar::Platform`], [`AuxVar::BasePlatform`], and
+    /// [`AuxVar::ExecFn`] as owned [`std::ffi::CString`], e.g., for FFI. An optional terminating
+    /// null byte is removed before. Returns `None` for all other variants and an error, if the
+    /// string contains interior null bytes.
+    #[cfg(feature = "std")]
+    pub fn into_cstring(self) -> Option<Result<std::ffi::CString, std::ffi::NulError>> {
+        self.value_payload_cstr()
+            .map(|cstr| std::ffi::CString::new(cstr.strip_suffix('\0').unwrap_or(cstr)))
+    }
+
+    /// Compares the key and the payload of two entries. In contrast to [`PartialEq`], which
+    /// only compares the key, this also compares the values. C-strings are equal, if they
+    /// only differ in the optional terminating null byte.
+    pub fn eq_content(&self, other: &Self) -> bool {
+        fn trim_null(cstr: &str) -> &str {
+            cstr.strip_suffix('\0').unwrap_or(cstr)
+        }
+        match (self, other) {
+            (AuxVar::Platform(a), AuxVar::Platform(b))
+            | (AuxVar::BasePlatform(a), AuxVar::BasePlatform(b))
+            | (AuxVar::ExecFn(a), AuxVar::ExecFn(b)) => trim_null(a) == trim_null(b),
+            (AuxVar::Random(a), AuxVar::Random(b)) => a == b,
+            _ => self.key() == other.key() && self.value_raw() == other.value_raw(),
+        }
+    }
+
+    /// Applies `f` to the string payload of [`AuxVar::Platform`], [`AuxVar::BasePlatform`], and
+    /// [`AuxVar::ExecFn`] and returns the transformed entry. All other entries stay unchanged.
+    /// An optional terminating null byte is not passed to `f`.
+    ///
+    /// The new string is stored in `storage`, which the returned entry borrows. The previous
+    /// content of `storage` is replaced.
+    pub fn map_string<'b>(
+        self,
+        storage: &'b mut String,
+        f: impl FnOnce(&str) -> String,
+    ) -> AuxVar<'b> {
+        let cstr = match self {
+            AuxVar::Platform(val) | AuxVar::BasePlatform(val) | AuxVar::ExecFn(val) => val,
+            AuxVar::Random(bytes) => return AuxVar::Random(bytes),
+            AuxVar::Flags(flags) => return AuxVar::Flags(flags),
+            other => {
+                return AuxVar::from_immediate(other.key(), other.value_raw())
+                    .expect("all other variants don't reference any data")
+            }
+        };
+        *storage = f(cstr.strip_suffix('\0').unwrap_or(cstr));
+        match self.key() {
+            AuxVarType::Platform => AuxVar::Platform(storage),
+            AuxVarType::BasePlatform => AuxVar::BasePlatform(storage),
+            _ => AuxVar::ExecFn(storage),
+        }
+    }
+
     // #########################
     // helper methods to validate the object in the builder
 
@@ -398,6 +716,7 @@ impl<'a> AuxVar<'a> {
     }
 }
 
+// Only compares the key, to be consistent with `Ord`. See type documentation.
 impl<'a> PartialEq for AuxVar<'a> {
     fn eq(&self, other: &Self) -> bool {
         self.key() == other.key()
@@ -422,6 +741,7 @@ impl<'a> Ord for AuxVar<'a> {
 mod tests {
     use super::*;
     use std::collections::BTreeSet;
+    use std::vec::Vec;
 
     /// Tests that the ATNull entry always comes last in an ordered collection. This enables
     /// us to easily write all AT-VARs at once but keep the terminating null entry at the end.
@@ -456,4 +776,249 @@ mod tests {
         );
         assert_eq!(AuxVar::Random([0; 16]).data_area_serialize_byte_count(), 16);
     }
+
+    /// Equality and ordering must be consistent, i.e., both only consider the key.
+    /// Otherwise, a `BTreeSet` behaves inconsistently.
+    #[test]
+    fn test_aux_var_eq_ord_consistency() {
+        let a = AuxVar::Uid(1000);
+        let b = AuxVar::Uid(0);
+        assert_eq!(a.cmp(&b), Ordering::Equal);
+        assert_eq!(a, b);
+
+        let mut set = BTreeSet::new();
+        assert!(set.insert(AuxVar::Uid(1000)));
+        assert!(!set.insert(AuxVar::Uid(0)));
+        assert_eq!(set.len(), 1);
+        assert!(set.contains(&AuxVar::Uid(42)));
+        assert_eq!(set.iter().next().unwrap().value_integer(), Some(1000));
+
+        // `replace` is the way to update the payload
+        set.replace(AuxVar::Uid(0));
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.iter().next().unwrap().value_integer(), Some(0));
+    }
+
+    #[test]
+    fn test_pointer_getters() {
+        let phdr = AuxVar::Phdr(0x400040 as *const u8);
+        assert_eq!(phdr.as_phdr(), Some(0x400040 as *const u8));
+        assert_eq!(phdr.as_entry(), None);
+        assert_eq!(phdr.as_base(), None);
+
+        let vdso = AuxVar::SysinfoEhdr(0xf7ff_d000 as *const u8);
+        assert_eq!(vdso.as_sysinfo_ehdr(), Some(0xf7ff_d000 as *const u8));
+        assert_eq!(vdso.as_sysinfo(), None);
+
+        assert_eq!(AuxVar::Uid(0).as_phdr(), None);
+    }
+
+    #[test]
+    fn test_eq_content() {
+        assert!(AuxVar::Uid(1).eq_content(&AuxVar::Uid(1)));
+        assert!(!AuxVar::Uid(1).eq_content(&AuxVar::Uid(2)));
+        assert!(!AuxVar::Uid(1).eq_content(&AuxVar::Gid(1)));
+        assert!(AuxVar::ExecFn("./foo\0").eq_content(&AuxVar::ExecFn("./foo")));
+        assert!(!AuxVar::ExecFn("./foo").eq_content(&AuxVar::ExecFn("./bar")));
+        assert!(AuxVar::Random([1; 16]).eq_content(&AuxVar::Random([1; 16])));
+        assert!(!AuxVar::Random([1; 16]).eq_content(&AuxVar::Random([2; 16])));
+    }
+
+    #[test]
+    fn test_map_string() {
+        let chroot = |path: &str| format!("/chroot{}", path);
+        let mut storage = String::new();
+        let var = AuxVar::ExecFn("/usr/bin/foo\0").map_string(&mut storage, chroot);
+        assert!(var.eq_content(&AuxVar::ExecFn("/chroot/usr/bin/foo")));
+        let var = AuxVar::Platform("x86_64").map_string(&mut storage, |s| s.to_uppercase());
+        assert!(var.eq_content(&AuxVar::Platform("X86_64")));
+        assert_eq!(storage, "X86_64");
+        let var = AuxVar::Uid(1000).map_string(&mut storage, chroot);
+        assert!(var.eq_content(&AuxVar::Uid(1000)));
+        let var = AuxVar::Random([7; 16]).map_string(&mut storage, chroot);
+        assert!(var.eq_content(&AuxVar::Random([7; 16])));
+        // untouched for entries without a string
+        assert_eq!(storage, "X86_64");
+    }
+
+    #[test]
+    fn test_flags_raw() {
+        let bits = 0xdead_0001;
+        let var = AuxVar::flags_from_raw(bits);
+        assert_eq!(var.raw_flags(), Some(bits));
+        assert!(var
+            .value_flags()
+            .unwrap()
+            .contains(AuxVarFlags::PRESERVE_ARGV0));
+        assert_eq!(var.value_raw(), bits);
+        assert_eq!(AuxVar::Uid(1).raw_flags(), None);
+
+        let serialized = AuxVarSerialized::new(AuxVarType::Flags, bits);
+        let parsed = unsafe { AuxVar::from_serialized(&serialized) }.unwrap();
+        assert_eq!(parsed.raw_flags(), Some(bits));
+    }
+
+    #[test]
+    fn test_from_referenced() {
+        let var = AuxVar::from_referenced(AuxVarType::Platform, b"x86_64\0").unwrap();
+        assert!(var.eq_content(&AuxVar::Platform("x86_64")));
+        let var = AuxVar::from_referenced(AuxVarType::ExecFn, b"./foo").unwrap();
+        assert!(var.eq_content(&AuxVar::ExecFn("./foo")));
+
+        let random = [0xab; 16];
+        let var = AuxVar::from_referenced(AuxVarType::Random, &random).unwrap();
+        assert!(var.eq_content(&AuxVar::Random(random)));
+        assert_eq!(
+            AuxVar::from_referenced(AuxVarType::Random, &random[..15]),
+            Err(TypeMismatchError(AuxVarType::Random))
+        );
+
+        assert_eq!(
+            AuxVar::from_referenced(AuxVarType::Uid, &random),
+            Err(TypeMismatchError(AuxVarType::Uid))
+        );
+        assert_eq!(
+            AuxVar::from_referenced(AuxVarType::Platform, &[0xff, 0xfe]),
+            Err(TypeMismatchError(AuxVarType::Platform))
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_into_cstring() {
+        use std::ffi::CString;
+        let expected = |s: &str| Some(CString::new(s).unwrap());
+        assert_eq!(
+            AuxVar::Platform("x86_64")
+                .into_cstring()
+                .map(Result::unwrap),
+            expected("x86_64")
+        );
+        assert_eq!(
+            AuxVar::BasePlatform("i686\0")
+                .into_cstring()
+                .map(Result::unwrap),
+            expected("i686")
+        );
+        assert_eq!(
+            AuxVar::ExecFn("./foo").into_cstring().map(Result::unwrap),
+            expected("./foo")
+        );
+        assert!(AuxVar::ExecFn("./f\0oo").into_cstring().unwrap().is_err());
+        assert!(AuxVar::Uid(0).into_cstring().is_none());
+    }
+
+    #[test]
+    fn test_random() {
+        assert_eq!(AuxVar::Random([3; 16]).random(), Some(&[3; 16]));
+        assert_eq!(AuxVar::Uid(3).random(), None);
+        assert_eq!(AuxVar::Platform("x86_64").random(), None);
+    }
+
+    #[test]
+    fn test_from_serialized_invalid_utf8() {
+        let invalid = [b'a', b'b', 0xff, b'c', 0];
+        let aux = AuxVarSerialized::new(AuxVarType::Platform, invalid.as_ptr() as usize);
+        assert_eq!(
+            unsafe { AuxVar::from_serialized(&aux) }.unwrap_err(),
+            TypeMismatchError(AuxVarType::Platform)
+        );
+    }
+
+    #[test]
+    fn test_value() {
+        assert_eq!(AuxVar::Pagesz(4096).value(), AuxValue::Int(4096));
+        assert_eq!(AuxVar::Secure(true).value(), AuxValue::Bool(true));
+        assert_eq!(
+            AuxVar::Flags(AuxVarFlags::PRESERVE_ARGV0).value(),
+            AuxValue::Flags(AuxVarFlags::PRESERVE_ARGV0)
+        );
+        assert_eq!(
+            AuxVar::Entry(0x1000 as *const u8).value(),
+            AuxValue::Ptr(0x1000 as *const u8)
+        );
+        assert_eq!(AuxVar::Platform("x86_64").value(), AuxValue::Str("x86_64"));
+        assert_eq!(AuxVar::Random([7; 16]).value(), AuxValue::Random([7; 16]));
+        assert_eq!(AuxVar::Null.value(), AuxValue::None);
+    }
+
+    #[test]
+    fn test_flags_iter_names() {
+        assert_eq!(
+            AuxVarFlags::PRESERVE_ARGV0.iter_names().collect::<Vec<_>>(),
+            ["PRESERVE_ARGV0"]
+        );
+        assert_eq!(AuxVarFlags::NOT_PRESERVE_ARGV0.iter_names().count(), 0);
+        assert_eq!(
+            unsafe { AuxVarFlags::from_bits_unchecked(0b11) }
+                .iter_names()
+                .collect::<Vec<_>>(),
+            ["PRESERVE_ARGV0"]
+        );
+    }
+
+    #[test]
+    fn test_hwcap2_x86() {
+        // value of an Intel CPU with FSGSBASE support
+        let hwcap2 = AuxVar::HwCap2(0x2).hwcap2_x86().unwrap();
+        assert_eq!(hwcap2, HwCap2X86::FSGSBASE);
+        assert!(hwcap2.contains(HwCap2X86::FSGSBASE));
+        assert!(!hwcap2.contains(HwCap2X86::RING3MWAIT));
+
+        let hwcap2 = AuxVar::HwCap2(0b111).hwcap2_x86().unwrap();
+        assert!(hwcap2.contains(HwCap2X86::RING3MWAIT | HwCap2X86::FSGSBASE));
+        assert_eq!(hwcap2.bits(), 0b111);
+
+        assert_eq!(AuxVar::HwCap(0x2).hwcap2_x86(), None);
+    }
+
+    #[test]
+    fn test_try_value_raw() {
+        assert_eq!(AuxVar::Pagesz(4096).try_value_raw(), Ok(4096));
+        assert_eq!(AuxVar::Secure(true).try_value_raw(), Ok(1));
+        assert_eq!(AuxVar::Null.try_value_raw(), Ok(0));
+        for var in [
+            AuxVar::Platform("x86_64\0"),
+            AuxVar::BasePlatform("x86_64\0"),
+            AuxVar::Random([0; 16]),
+            AuxVar::ExecFn("./executable\0"),
+        ] {
+            assert_eq!(var.try_value_raw(), Err(DataAreaValueError(var.key())));
+        }
+    }
+
+    #[test]
+    fn test_addr_constructors() {
+        let vars = [
+            AuxVar::phdr(0x40_0040),
+            AuxVar::base(0x7f00_0000),
+            AuxVar::entry(0x40_1560),
+            AuxVar::sysinfo(0x7fff_1000),
+            AuxVar::sysinfo_ehdr(0x7fff_2000),
+        ];
+        let keys = vars.iter().map(AuxVar::key).collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                AuxVarType::Phdr,
+                AuxVarType::Base,
+                AuxVarType::Entry,
+                AuxVarType::Sysinfo,
+                AuxVarType::SysinfoEhdr
+            ]
+        );
+        let addrs = vars.iter().map(AuxVar::as_addr).collect::<Vec<_>>();
+        assert_eq!(
+            addrs,
+            [
+                Some(0x40_0040),
+                Some(0x7f00_0000),
+                Some(0x40_1560),
+                Some(0x7fff_1000),
+                Some(0x7fff_2000)
+            ]
+        );
+        assert!(AuxVar::entry(0x1000).eq_content(&AuxVar::Entry(0x1000 as *const u8)));
+        assert_eq!(AuxVar::Pagesz(4096).as_addr(), None);
+    }
 }
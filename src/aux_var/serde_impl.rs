@@ -0,0 +1,286 @@
+/*
+MIT License
+
+Copyright (c) 2021 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Implementations of [`serde::Serialize`] and [`serde::Deserialize`] for [`AuxVar`]. The
+//! entry is represented by its key and its value (see [`AuxValue`]). Pointers are represented
+//! as hex strings and C-strings as UTF-8 strings without the terminating null byte, together
+//! with a flag whether it was present. Hence, the round trip is lossless.
+//!
+//! [`AuxVar`] only borrows C-strings from the input. Entries whose string can't be borrowed are
+//! deserialized into [`AuxVarOwned`].
+
+use super::{AuxValue, AuxVar, AuxVarFlags, AuxVarType};
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serialized form of an [`AuxVar`].
+#[derive(Serialize, Deserialize)]
+struct AuxVarRepr<'a> {
+    key: AuxVarType,
+    #[serde(borrow)]
+    value: AuxValueRepr<'a>,
+}
+
+/// Serialized form of an [`AuxValue`].
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum AuxValueRepr<'a> {
+    Int(usize),
+    Bool(bool),
+    Flags(AuxVarFlags),
+    /// Hex string with `0x` prefix.
+    Ptr(String),
+    /// C-string without the terminating null byte.
+    Str {
+        #[serde(borrow)]
+        value: Cow<'a, str>,
+        /// Whether the original string had a terminating null byte.
+        nul: bool,
+    },
+    Random([u8; 16]),
+    None,
+}
+
+impl<'a> From<AuxValue<'a>> for AuxValueRepr<'a> {
+    fn from(value: AuxValue<'a>) -> Self {
+        match value {
+            AuxValue::Int(val) => Self::Int(val),
+            AuxValue::Bool(val) => Self::Bool(val),
+            AuxValue::Flags(flags) => Self::Flags(flags),
+            AuxValue::Ptr(ptr) => Self::Ptr(format!("{:#x}", ptr as usize)),
+            AuxValue::Str(cstr) => Self::Str {
+                value: Cow::Borrowed(cstr.strip_suffix('\0').unwrap_or(cstr)),
+                nul: cstr.ends_with('\0'),
+            },
+            AuxValue::Random(bytes) => Self::Random(bytes),
+            AuxValue::None => Self::None,
+        }
+    }
+}
+
+impl Serialize for AuxVar<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        AuxVarRepr {
+            key: self.key(),
+            value: self.value().into(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Creates the entry for a key whose value is a C-string.
+const fn cstr_var(key: AuxVarType, cstr: &str) -> AuxVar<'_> {
+    match key {
+        AuxVarType::Platform => AuxVar::Platform(cstr),
+        AuxVarType::BasePlatform => AuxVar::BasePlatform(cstr),
+        _ => AuxVar::ExecFn(cstr),
+    }
+}
+
+/// Creates the entry for a value that doesn't reference a C-string.
+fn non_cstr_var<E: Error>(key: AuxVarType, value: AuxValueRepr) -> Result<AuxVar<'static>, E> {
+    let mismatch = || E::custom(format!("invalid value for {:?}", key));
+    let shape = core::mem::discriminant(&value);
+    let raw = match value {
+        AuxValueRepr::Random(bytes) if key == AuxVarType::Random => {
+            return Ok(AuxVar::Random(bytes));
+        }
+        _ if key.value_in_data_area() => return Err(mismatch()),
+        AuxValueRepr::Int(val) => val,
+        AuxValueRepr::Bool(val) => val as usize,
+        AuxValueRepr::Flags(flags) => flags.bits(),
+        AuxValueRepr::Ptr(ptr) => ptr
+            .strip_prefix("0x")
+            .and_then(|hex| usize::from_str_radix(hex, 16).ok())
+            .ok_or_else(mismatch)?,
+        AuxValueRepr::Str { .. } | AuxValueRepr::Random(_) | AuxValueRepr::None => 0,
+    };
+    let var = AuxVar::from_immediate(key, raw).ok_or_else(mismatch)?;
+    // the shape of the value must match the key, e.g., no boolean for `AT_PAGESZ`
+    if core::mem::discriminant(&AuxValueRepr::from(var.value())) == shape {
+        Ok(var)
+    } else {
+        Err(mismatch())
+    }
+}
+
+/// Deserializes an [`AuxVar`] without copying. C-strings are borrowed from the input.
+///
+/// # Errors
+/// Fails for C-strings that can't be borrowed, i.e., strings with escape sequences or with a
+/// terminating null byte. Use [`AuxVarOwned`] for them.
+impl<'de: 'a, 'a> Deserialize<'de> for AuxVar<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let AuxVarRepr { key, value } = AuxVarRepr::deserialize(deserializer)?;
+        match value {
+            AuxValueRepr::Str { value, nul } if key.value_is_cstr() => match (value, nul) {
+                (Cow::Borrowed(cstr), false) => Ok(cstr_var(key, cstr)),
+                _ => Err(D::Error::custom(format!(
+                    "value of {:?} can't be borrowed from the input, use AuxVarOwned",
+                    key
+                ))),
+            },
+            value => non_cstr_var(key, value),
+        }
+    }
+}
+
+/// Owned counterpart of an [`AuxVar`] for deserialization.
+///
+/// Unlike [`AuxVar`], it can be deserialized from any input, as it owns the C-string of the
+/// entry, if there is one. Use [`AuxVarOwned::as_aux_var`] to get the entry.
+#[derive(Debug)]
+pub struct AuxVarOwned {
+    /// The entry. For C-string entries, the string is empty and lives in `cstr`.
+    var: AuxVar<'static>,
+    /// The C-string of the entry, including the optional terminating null byte.
+    cstr: String,
+}
+
+impl AuxVarOwned {
+    /// Returns the entry, which borrows the C-string from `self`.
+    pub fn as_aux_var(&self) -> AuxVar<'_> {
+        match &self.var {
+            var if var.key().value_is_cstr() => cstr_var(var.key(), &self.cstr),
+            AuxVar::Random(bytes) => AuxVar::Random(*bytes),
+            var => AuxVar::from_immediate(var.key(), var.value_raw())
+                .expect("all other variants don't reference any data"),
+        }
+    }
+}
+
+impl Serialize for AuxVarOwned {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_aux_var().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AuxVarOwned {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let AuxVarRepr { key, value } = AuxVarRepr::deserialize(deserializer)?;
+        match value {
+            AuxValueRepr::Str { value, nul } if key.value_is_cstr() => {
+                let mut cstr = value.into_owned();
+                if nul {
+                    cstr.push('\0');
+                }
+                Ok(Self {
+                    var: cstr_var(key, ""),
+                    cstr,
+                })
+            }
+            value => Ok(Self {
+                var: non_cstr_var(key, value)?,
+                cstr: String::new(),
+            }),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde_json"))]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let vars = [
+            AuxVar::Pagesz(4096),
+            AuxVar::Secure(true),
+            AuxVar::Flags(AuxVarFlags::PRESERVE_ARGV0),
+            AuxVar::entry(0x40_1560),
+            AuxVar::Platform("x86_64"),
+            AuxVar::ExecFn("./executable\0"),
+            AuxVar::Random([7; 16]),
+            AuxVar::Null,
+        ];
+        for var in &vars {
+            let json = serde_json::to_string(var).unwrap();
+            let owned: AuxVarOwned = serde_json::from_str(&json).unwrap();
+            let deserialized = owned.as_aux_var();
+            assert!(deserialized.eq_content(var), "{}", json);
+            // the optional null byte is preserved
+            assert_eq!(deserialized.value_payload_cstr(), var.value_payload_cstr());
+            assert_eq!(serde_json::to_string(&owned).unwrap(), json);
+
+            // zero-copy deserialization only works without the null byte
+            match serde_json::from_str::<AuxVar>(&json) {
+                Ok(deserialized) => assert!(deserialized.eq_content(var), "{}", json),
+                Err(_) => assert_eq!(var.value_payload_cstr(), Some("./executable\0")),
+            }
+        }
+
+        let json = serde_json::to_string(&vars[3]).unwrap();
+        assert_eq!(json, r#"{"key":"Entry","value":{"ptr":"0x401560"}}"#);
+        let json = serde_json::to_string(&vars[5]).unwrap();
+        assert_eq!(
+            json,
+            r#"{"key":"ExecFn","value":{"str":{"value":"./executable","nul":true}}}"#
+        );
+
+        let types = vars.iter().map(AuxVar::key).collect::<Vec<_>>();
+        let json = serde_json::to_string(&types).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Vec<AuxVarType>>(&json).unwrap(),
+            types
+        );
+    }
+
+    #[test]
+    fn test_serde_value_mismatch() {
+        let invalid = [
+            r#"{"key":"Pagesz","value":{"bool":true}}"#,
+            r#"{"key":"Platform","value":{"int":1}}"#,
+            r#"{"key":"Random","value":{"str":{"value":"x","nul":false}}}"#,
+            r#"{"key":"Entry","value":{"ptr":"401560"}}"#,
+        ];
+        for json in invalid {
+            assert!(serde_json::from_str::<AuxVar>(json).is_err(), "{}", json);
+            assert!(
+                serde_json::from_str::<AuxVarOwned>(json).is_err(),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn test_serde_borrowed_cstr() {
+        let json = r#"{"key":"Platform","value":{"str":{"value":"x86_64","nul":false}}}"#;
+        let var = serde_json::from_str::<AuxVar>(json).unwrap();
+        assert!(var.eq_content(&AuxVar::Platform("x86_64")));
+        assert!(core::ptr::eq(
+            var.value_payload_cstr().unwrap().as_ptr(),
+            json[json.find("x86_64").unwrap()..].as_ptr()
+        ));
+
+        // escape sequences can't be borrowed
+        let json = r#"{"key":"Platform","value":{"str":{"value":"x86\u005f64","nul":false}}}"#;
+        assert!(serde_json::from_str::<AuxVar>(json).is_err());
+        let owned = serde_json::from_str::<AuxVarOwned>(json).unwrap();
+        assert!(owned.as_aux_var().eq_content(&AuxVar::Platform("x86_64")));
+    }
+}
@@ -1,6 +1,8 @@
 use crate::aux_var::AuxVarType;
+use crate::cstr_util::c_str_len_ptr;
 use core::fmt::{Debug, Formatter};
 use core::marker::PhantomData;
+use core::str::Utf8Error;
 
 /// Serialized form of an auxiliary vector entry / an AT variable. Each entry is a
 /// `(usize, usize)`-pair in memory. The lifetime is bound to the one of the buffer,
@@ -15,6 +17,15 @@ pub struct AuxVarSerialized<'a> {
 }
 
 impl<'a> AuxVarSerialized<'a> {
+    /// Creates a new entry from a key and its raw value.
+    pub(crate) const fn new(key: AuxVarType, val: usize) -> Self {
+        Self {
+            key,
+            val,
+            _marker: PhantomData,
+        }
+    }
+
     /// Returns the key.
     pub const fn key(&self) -> AuxVarType {
         self.key
@@ -24,6 +35,28 @@ impl<'a> AuxVarSerialized<'a> {
     pub const fn val(&self) -> usize {
         self.val
     }
+
+    /// Returns the C-string the value points to (up to the first NUL byte) as `&str`, if the
+    /// key refers to a C-string in the data area. Invalid UTF-8 results in an error.
+    ///
+    /// # Safety
+    /// The value must be a valid pointer to a null-terminated C-string in the address space
+    /// of the caller.
+    pub unsafe fn as_str(&self) -> Option<Result<&'a str, Utf8Error>> {
+        if !self.key().value_is_cstr() {
+            return None;
+        }
+        Some(core::str::from_utf8(self.cstr_bytes()))
+    }
+
+    /// Returns the bytes of the C-string the value points to, without the NUL byte.
+    ///
+    /// # Safety
+    /// See [`Self::as_str`].
+    pub(crate) unsafe fn cstr_bytes(&self) -> &'a [u8] {
+        let ptr = self.val() as *const u8;
+        core::slice::from_raw_parts(ptr, c_str_len_ptr(ptr))
+    }
 }
 
 impl<'a> Debug for AuxVarSerialized<'a> {
@@ -49,4 +82,19 @@ mod tests {
         #[cfg(target_arch = "x86_64")]
         assert_eq!(size_of::<AuxVarSerialized>(), 16);
     }
+
+    #[test]
+    fn test_as_str() {
+        let platform = b"x86_64\0";
+        let aux = AuxVarSerialized::new(AuxVarType::Platform, platform.as_ptr() as usize);
+        assert_eq!(unsafe { aux.as_str() }, Some(Ok("x86_64")));
+
+        let invalid = [b'a', 0xff, 0xfe, 0];
+        let aux = AuxVarSerialized::new(AuxVarType::ExecFn, invalid.as_ptr() as usize);
+        let err = unsafe { aux.as_str() }.unwrap().unwrap_err();
+        assert_eq!(err.valid_up_to(), 1);
+
+        let aux = AuxVarSerialized::new(AuxVarType::Pagesz, 4096);
+        assert_eq!(unsafe { aux.as_str() }, None);
+    }
 }
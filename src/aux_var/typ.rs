@@ -21,6 +21,7 @@ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
+use crate::ParseAuxVarTypeError;
 use core::cmp::Ordering;
 use enum_iterator::IntoEnumIterator;
 
@@ -35,6 +36,7 @@ use enum_iterator::IntoEnumIterator;
 /// * <https://elixir.bootlin.com/linux/latest/source/fs/binfmt_elf.c#L259>
 /// * <https://man7.org/linux/man-pages/man3/getauxval.3.html>
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoEnumIterator)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(usize)]
 pub enum AuxVarType {
     // ### architecture neutral
@@ -84,6 +86,14 @@ pub enum AuxVarType {
     Random = 25,
     /// extension of AtHwcap
     HwCap2 = 26,
+    /// size of the `struct rseq` features, that the kernel supports
+    RseqFeatureSize = 27,
+    /// required alignment of `struct rseq`
+    RseqAlign = 28,
+    /// extension of AtHwcap2
+    HwCap3 = 29,
+    /// extension of AtHwcap3
+    HwCap4 = 30,
     /// filename of program, for example "./my_executable\0"
     ExecFn = 31,
 
@@ -116,6 +126,13 @@ impl AuxVarType {
         self as _
     }
 
+    /// Returns true, if both types occupy the same slot in the ABI, i.e., have the same
+    /// numeric value (see [`Self::val`]). This makes the intent explicit in code that maps
+    /// types across architectures.
+    pub const fn same_abi_slot(&self, other: &Self) -> bool {
+        self.val() == other.val()
+    }
+
     /// If this is true, the value of the key should be interpreted as pointer into
     /// the aux vector data area. Otherwise, the value of the key is an immediate value/integer.
     // TODO move to AuxVar?!
@@ -148,6 +165,10 @@ impl AuxVarType {
             // references random bytes
             AuxVarType::Random => true,
             AuxVarType::HwCap2 => false,
+            AuxVarType::RseqFeatureSize => false,
+            AuxVarType::RseqAlign => false,
+            AuxVarType::HwCap3 => false,
+            AuxVarType::HwCap4 => false,
             // references C-str
             AuxVarType::ExecFn => true,
             AuxVarType::SysinfoEhdr => false,
@@ -180,16 +201,194 @@ impl AuxVarType {
             _ => None,
         }
     }
+
+    /// Returns the size in bytes of a serialized `(key, value)`-pair in the auxiliary vector
+    /// of a target with the given word size. This is independent of the type.
+    pub const fn serialized_pair_size(word_size: WordSize) -> usize {
+        2 * word_size.bytes()
+    }
+
+    /// Returns a short description of the type, similar to the comments in the Linux source
+    /// code and the man page of `getauxval`.
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::Null => "end of vector",
+            Self::Ignore => "entry should be ignored",
+            Self::ExecFd => "file descriptor of program",
+            Self::Phdr => "program headers for program",
+            Self::Phent => "size of program header entry",
+            Self::Phnum => "number of program headers",
+            Self::Pagesz => "system page size",
+            Self::Base => "base address of the program interpreter",
+            Self::Flags => "flags",
+            Self::Entry => "entry point of program",
+            Self::NotElf => "program is not ELF",
+            Self::Uid => "real uid",
+            Self::EUid => "effective uid",
+            Self::Gid => "real gid",
+            Self::EGid => "effective gid",
+            Self::Platform => "string identifying CPU for optimizations",
+            Self::HwCap => "arch dependent hints at CPU capabilities",
+            Self::Clktck => "frequency at which times() increments",
+            Self::Secure => "secure mode boolean",
+            Self::BasePlatform => "string identifying real platform",
+            Self::Random => "address of 16 random bytes",
+            Self::HwCap2 => "extension of AT_HWCAP",
+            Self::RseqFeatureSize => "rseq supported feature size",
+            Self::RseqAlign => "rseq allocation alignment",
+            Self::HwCap3 => "extension of AT_HWCAP2",
+            Self::HwCap4 => "extension of AT_HWCAP3",
+            Self::ExecFn => "filename of program",
+            Self::Sysinfo => "entry point to the system call function in the vDSO",
+            Self::SysinfoEhdr => "address of the vDSO",
+            Self::L1iCacheSize => "L1 instruction cache size",
+            Self::L1iCacheGeometry => "L1 instruction cache geometry",
+            Self::L1dCacheSize => "L1 data cache size",
+            Self::L1dCacheGeometry => "L1 data cache geometry",
+            Self::L2CacheSize => "L2 cache size",
+            Self::L2CacheGeometry => "L2 cache geometry",
+            Self::L3CacheSize => "L3 cache size",
+            Self::L3CacheGeometry => "L3 cache geometry",
+            Self::MinSigStkSz => "minimal stack size for signal delivery",
+        }
+    }
+
+    /// Returns the name of the constant in the Linux source code, e.g., `AT_PAGESZ`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Null => "AT_NULL",
+            Self::Ignore => "AT_IGNORE",
+            Self::ExecFd => "AT_EXECFD",
+            Self::Phdr => "AT_PHDR",
+            Self::Phent => "AT_PHENT",
+            Self::Phnum => "AT_PHNUM",
+            Self::Pagesz => "AT_PAGESZ",
+            Self::Base => "AT_BASE",
+            Self::Flags => "AT_FLAGS",
+            Self::Entry => "AT_ENTRY",
+            Self::NotElf => "AT_NOTELF",
+            Self::Uid => "AT_UID",
+            Self::EUid => "AT_EUID",
+            Self::Gid => "AT_GID",
+            Self::EGid => "AT_EGID",
+            Self::Platform => "AT_PLATFORM",
+            Self::HwCap => "AT_HWCAP",
+            Self::Clktck => "AT_CLKTCK",
+            Self::Secure => "AT_SECURE",
+            Self::BasePlatform => "AT_BASE_PLATFORM",
+            Self::Random => "AT_RANDOM",
+            Self::HwCap2 => "AT_HWCAP2",
+            Self::RseqFeatureSize => "AT_RSEQ_FEATURE_SIZE",
+            Self::RseqAlign => "AT_RSEQ_ALIGN",
+            Self::HwCap3 => "AT_HWCAP3",
+            Self::HwCap4 => "AT_HWCAP4",
+            Self::ExecFn => "AT_EXECFN",
+            Self::Sysinfo => "AT_SYSINFO",
+            Self::SysinfoEhdr => "AT_SYSINFO_EHDR",
+            Self::L1iCacheSize => "AT_L1I_CACHESIZE",
+            Self::L1iCacheGeometry => "AT_L1I_CACHEGEOMETRY",
+            Self::L1dCacheSize => "AT_L1D_CACHESIZE",
+            Self::L1dCacheGeometry => "AT_L1D_CACHEGEOMETRY",
+            Self::L2CacheSize => "AT_L2_CACHESIZE",
+            Self::L2CacheGeometry => "AT_L2_CACHEGEOMETRY",
+            Self::L3CacheSize => "AT_L3_CACHESIZE",
+            Self::L3CacheGeometry => "AT_L3_CACHEGEOMETRY",
+            Self::MinSigStkSz => "AT_MINSIGSTKSZ",
+        }
+    }
+
+    /// Returns the [`AuxVarCategory`] this type belongs to.
+    pub const fn category(self) -> AuxVarCategory {
+        match self {
+            Self::Null => AuxVarCategory::Terminator,
+            Self::Ignore => AuxVarCategory::Other,
+            Self::ExecFd => AuxVarCategory::Program,
+            Self::Phdr => AuxVarCategory::Program,
+            Self::Phent => AuxVarCategory::Program,
+            Self::Phnum => AuxVarCategory::Program,
+            Self::Pagesz => AuxVarCategory::Memory,
+            Self::Base => AuxVarCategory::Program,
+            Self::Flags => AuxVarCategory::Program,
+            Self::Entry => AuxVarCategory::Program,
+            Self::NotElf => AuxVarCategory::Program,
+            Self::Uid => AuxVarCategory::Identity,
+            Self::EUid => AuxVarCategory::Identity,
+            Self::Gid => AuxVarCategory::Identity,
+            Self::EGid => AuxVarCategory::Identity,
+            Self::Platform => AuxVarCategory::Cpu,
+            Self::HwCap => AuxVarCategory::Cpu,
+            Self::Clktck => AuxVarCategory::Kernel,
+            Self::Secure => AuxVarCategory::Identity,
+            Self::BasePlatform => AuxVarCategory::Cpu,
+            Self::Random => AuxVarCategory::Kernel,
+            Self::HwCap2 => AuxVarCategory::Cpu,
+            Self::RseqFeatureSize => AuxVarCategory::Memory,
+            Self::RseqAlign => AuxVarCategory::Memory,
+            Self::HwCap3 => AuxVarCategory::Cpu,
+            Self::HwCap4 => AuxVarCategory::Cpu,
+            Self::ExecFn => AuxVarCategory::Program,
+            Self::Sysinfo => AuxVarCategory::Kernel,
+            Self::SysinfoEhdr => AuxVarCategory::Kernel,
+            Self::L1iCacheSize => AuxVarCategory::Cpu,
+            Self::L1iCacheGeometry => AuxVarCategory::Cpu,
+            Self::L1dCacheSize => AuxVarCategory::Cpu,
+            Self::L1dCacheGeometry => AuxVarCategory::Cpu,
+            Self::L2CacheSize => AuxVarCategory::Cpu,
+            Self::L2CacheGeometry => AuxVarCategory::Cpu,
+            Self::L3CacheSize => AuxVarCategory::Cpu,
+            Self::L3CacheGeometry => AuxVarCategory::Cpu,
+            Self::MinSigStkSz => AuxVarCategory::Memory,
+        }
+    }
+}
+
+/// Coarse classification of the [`AuxVarType`]s. See [`AuxVarType::category`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AuxVarCategory {
+    /// The terminating [`AuxVarType::Null`] entry.
+    Terminator,
+    /// Information about the loaded program, such as the program headers, the entry point,
+    /// or the file name.
+    Program,
+    /// Real and effective user and group IDs and the secure mode.
+    Identity,
+    /// Page size and stack size requirements.
+    Memory,
+    /// CPU capabilities, platform strings, and cache information.
+    Cpu,
+    /// Information provided by the kernel, such as the vDSO, the clock tick, and random bytes.
+    Kernel,
+    /// Entries without a meaning, such as [`AuxVarType::Ignore`].
+    Other,
+}
+
+/// Word size (width of `usize` and of pointers) of the target that a data structure is
+/// created for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum WordSize {
+    /// 32-bit targets, such as x86.
+    Bits32,
+    /// 64-bit targets, such as x86_64.
+    Bits64,
 }
 
-impl From<usize> for AuxVarType {
-    fn from(val: usize) -> Self {
-        for variant in Self::into_enum_iter() {
-            if variant.val() == val {
-                return variant;
-            }
+impl WordSize {
+    /// Returns the word size in bytes.
+    pub const fn bytes(self) -> usize {
+        match self {
+            Self::Bits32 => 4,
+            Self::Bits64 => 8,
         }
-        panic!("invalid variant {}", val);
+    }
+}
+
+impl TryFrom<usize> for AuxVarType {
+    type Error = ParseAuxVarTypeError;
+
+    fn try_from(val: usize) -> Result<Self, Self::Error> {
+        Self::into_enum_iter()
+            .find(|variant| variant.val() == val)
+            .ok_or(ParseAuxVarTypeError(val))
     }
 }
 
@@ -197,6 +396,8 @@ impl PartialOrd for AuxVarType {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         if matches!(self, Self::Null) && !matches!(other, Self::Null) {
             Some(Ordering::Greater)
+        } else if !matches!(self, Self::Null) && matches!(other, Self::Null) {
+            Some(Ordering::Less)
         } else {
             self.val().partial_cmp(&other.val())
         }
@@ -213,6 +414,7 @@ impl Ord for AuxVarType {
 mod tests {
     use super::*;
     use std::collections::BTreeSet;
+    use std::vec::Vec;
 
     /// Tests that the ATNull entry always comes last in an ordered collection. This enables
     /// us to easily write all AT-VARs at once but keep the terminating null entry at the end.
@@ -226,4 +428,64 @@ mod tests {
         set.insert(AuxVarType::ExecFn);
         assert_eq!(set.into_iter().last().unwrap(), AuxVarType::Null);
     }
+
+    /// Tests that the order is also correct if `Null` is inserted before keys with a higher
+    /// numeric value.
+    #[test]
+    fn test_aux_var_key_order_null_first() {
+        let mut set = BTreeSet::new();
+        set.insert(AuxVarType::Null);
+        set.insert(AuxVarType::MinSigStkSz);
+        set.insert(AuxVarType::Pagesz);
+        assert_eq!(set.into_iter().last().unwrap(), AuxVarType::Null);
+        assert_eq!(
+            AuxVarType::MinSigStkSz.cmp(&AuxVarType::Null),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn test_serialized_pair_size() {
+        assert_eq!(AuxVarType::serialized_pair_size(WordSize::Bits32), 8);
+        assert_eq!(AuxVarType::serialized_pair_size(WordSize::Bits64), 16);
+    }
+
+    #[test]
+    fn test_description() {
+        assert_eq!(AuxVarType::Pagesz.description(), "system page size");
+        assert_eq!(AuxVarType::Null.description(), "end of vector");
+        assert_eq!(AuxVarType::ExecFn.description(), "filename of program");
+        assert!(AuxVarType::into_enum_iter().all(|typ| !typ.description().is_empty()));
+    }
+
+    #[test]
+    fn test_name() {
+        assert_eq!(AuxVarType::Pagesz.name(), "AT_PAGESZ");
+        assert_eq!(AuxVarType::SysinfoEhdr.name(), "AT_SYSINFO_EHDR");
+        assert!(AuxVarType::into_enum_iter().all(|typ| typ.name().starts_with("AT_")));
+    }
+
+    #[test]
+    fn test_same_abi_slot() {
+        assert!(AuxVarType::Pagesz.same_abi_slot(&AuxVarType::Pagesz));
+        assert!(!AuxVarType::Pagesz.same_abi_slot(&AuxVarType::Phnum));
+        assert!(AuxVarType::into_enum_iter()
+            .all(|a| AuxVarType::into_enum_iter().all(|b| a.same_abi_slot(&b) == (a == b))));
+    }
+
+    #[test]
+    fn test_variants_are_sorted() {
+        let variants = AuxVarType::into_enum_iter().collect::<Vec<_>>();
+        assert!(variants
+            .windows(2)
+            .all(|pair| pair[0].val() < pair[1].val()));
+    }
+
+    #[test]
+    fn test_try_from() {
+        assert_eq!(AuxVarType::try_from(6), Ok(AuxVarType::Pagesz));
+        assert_eq!(AuxVarType::try_from(0), Ok(AuxVarType::Null));
+        assert_eq!(AuxVarType::try_from(999), Err(ParseAuxVarTypeError(999)));
+        assert!(AuxVarType::into_enum_iter().all(|typ| AuxVarType::try_from(typ.val()) == Ok(typ)));
+    }
 }
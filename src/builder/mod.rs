@@ -23,14 +23,20 @@ SOFTWARE.
 */
 //! Module for [`InitialLinuxLibcStackLayoutBuilder`].
 mod serializer;
+mod strict;
 
 use serializer::*;
+pub use strict::*;
 
 use crate::cstr_util::{cstr_contains_at_most_terminating_null_byte, cstr_len_with_nullbyte};
-use crate::{AuxVar, AuxVarSerialized, AuxVarType};
+use crate::layout_math;
+use crate::{
+    AuxVar, AuxVarCategory, AuxVarType, BuildError, EnvSyntaxError, LayoutError, TypeMismatchError,
+    WordSize,
+};
 use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
-use core::mem::size_of;
+use core::mem::{align_of, size_of};
 
 /// Builder to construct the stack layout that a libc implementation under Linux initially
 /// expects. See <https://lwn.net/Articles/631631/> for more info. It helps to write the
@@ -45,9 +51,78 @@ pub struct InitialLinuxLibcStackLayoutBuilder<'a> {
     env_v: Vec<&'a str>,
     /// List of (key=value)-pairs for the auxiliary vector.
     aux_v: BTreeSet<AuxVar<'a>>,
+    /// Alignment of the aux data area, if it is bigger than the default of 16 bytes.
+    /// See [`Self::page_align_data`].
+    data_area_alignment: Option<usize>,
+    /// Whether identical argv or envv strings share one copy in the data area.
+    /// See [`Self::dedup_strings`].
+    dedup_strings: bool,
+    /// Whether `argc` must be placed at a 16-byte aligned address. See [`Self::abi_aligned`].
+    abi_aligned: bool,
+    /// Total size including trailing zero padding. See [`Self::pad_to`].
+    padded_size: Option<usize>,
+    /// Location of the C-string of [`AuxVarType::ExecFn`]. See [`Self::execfn_placement`].
+    execfn_placement: ExecFnPlacement,
+    /// Whether an additional zero word follows the terminating [`AuxVarType::Null`] entry.
+    /// See [`Self::emit_trailing_sentinel`].
+    trailing_sentinel: bool,
+}
+
+/// Number of padding bytes that [`InitialLinuxLibcStackLayoutBuilder`] inserts to align the
+/// data areas. See [`InitialLinuxLibcStackLayoutBuilder::alignment_padding`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AlignmentPadding {
+    /// Padding between the auxiliary vector and the aux data area.
+    pub before_aux_data: usize,
+    /// Padding between the aux data area and the argv data area.
+    pub before_argv_data: usize,
+}
+
+/// The libc implementation that is expected to parse the data structure. Used by
+/// [`InitialLinuxLibcStackLayoutBuilder::libc_target`] to add entries to the auxiliary
+/// vector, which the libc relies on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LibcTarget {
+    /// No adjustments.
+    Generic,
+    /// Ensures that [`AuxVarType::Random`] is present, because glibc dereferences it
+    /// unconditionally to set up the stack protector canary and the pointer guard. The given
+    /// bytes are used, if the entry is missing. They should come from a secure source of
+    /// randomness, as predictable bytes defeat the stack protector.
+    Glibc {
+        /// The bytes for [`AuxVarType::Random`].
+        random: [u8; 16],
+    },
+    /// Ensures that [`AuxVarType::Pagesz`] and [`AuxVarType::Clktck`] are present, because
+    /// musl takes the page size and the clock ticks only from the auxiliary vector and has
+    /// no fallback for a missing entry.
+    Musl,
+}
+
+/// Location of the C-string of [`AuxVarType::ExecFn`] in the data structure. See
+/// [`InitialLinuxLibcStackLayoutBuilder::execfn_placement`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExecFnPlacement {
+    /// At the top of the structure right below the final null word, as Linux does it.
+    /// This is the default.
+    TopOfStack,
+    /// In the aux data area along with the other referenced aux data.
+    DataArea,
+}
+
+// `#[default]` on enum variants requires a newer compiler than the MSRV
+#[allow(clippy::derivable_impls)]
+impl Default for ExecFnPlacement {
+    fn default() -> Self {
+        Self::TopOfStack
+    }
 }
 
 impl<'a> InitialLinuxLibcStackLayoutBuilder<'a> {
+    /// Value of [`AuxVarType::MinSigStkSz`] that [`Self::minsigstksz_from_host`] uses, if the
+    /// host doesn't report a value. This is the `MINSIGSTKSZ` constant of x86.
+    pub const DEFAULT_MINSIGSTKSZ: usize = 2048;
+
     /// Creates a new [`InitialLinuxLibcStackLayoutBuilder`]. The AUX entries [`AuxVarType::Null`]
     /// and [`AuxVarType::ExecFn`] will be always present.
     pub fn new() -> Self {
@@ -60,9 +135,32 @@ impl<'a> InitialLinuxLibcStackLayoutBuilder<'a> {
             arg_v: vec![],
             env_v: vec![],
             aux_v: map,
+            data_area_alignment: None,
+            dedup_strings: false,
+            abi_aligned: false,
+            padded_size: None,
+            execfn_placement: ExecFnPlacement::TopOfStack,
+            trailing_sentinel: false,
         }
     }
 
+    /// Creates a builder for a minimal data structure that a typical libc accepts. It has
+    /// `argv0` as only argument, no environment variables, and the auxiliary vector entries
+    /// [`AuxVarType::Pagesz`] (4096), [`AuxVarType::Clktck`] (100), [`AuxVarType::Random`]
+    /// (`random`), [`AuxVarType::ExecFn`] (`argv0`), and [`AuxVarType::Secure`] (false). This
+    /// is a starting point for tests; real programs need further entries, such as
+    /// [`AuxVarType::Phdr`] and [`AuxVarType::Entry`]. `random` should come from a secure
+    /// source of randomness, as libc derives the stack protector canary from it.
+    pub fn minimal_valid(argv0: &'a str, random: [u8; 16]) -> Self {
+        Self::new()
+            .add_arg_v(argv0)
+            .add_aux_v(AuxVar::Pagesz(4096))
+            .add_aux_v(AuxVar::Clktck(100))
+            .add_aux_v(AuxVar::Random(random))
+            .add_aux_v(AuxVar::ExecFn(argv0))
+            .add_aux_v(AuxVar::Secure(false))
+    }
+
     /// Serializes the data structure into the provided buffer.
     ///
     /// # Parameters
@@ -77,8 +175,223 @@ impl<'a> InitialLinuxLibcStackLayoutBuilder<'a> {
             write_buf.len() >= self.total_size(),
             "the buffer is not big enough!"
         );
+        assert_eq!(
+            self.check_abi_alignment(user_ptr),
+            Ok(()),
+            "the stack pointer violates the ABI alignment!"
+        );
+        write_buf[self.content_size()..self.total_size()].fill(0);
         let write_ptr = write_buf.as_mut_ptr();
-        let mut writer = AuxvSerializer::new(self, write_ptr, user_ptr);
+        self.serialize(AuxvSerializer::new(self, write_ptr, user_ptr));
+    }
+
+    /// Serializes the data structure into a new buffer of [`Self::total_size`] bytes. The
+    /// builder is not consumed, so that multiple variants can be derived from one template.
+    ///
+    /// # Parameters
+    /// * `user_ptr`: Stack pointer in user address space. If `None`, the address of the
+    ///   allocated buffer is used, i.e., the structure is valid in the address space of the
+    ///   caller.
+    pub fn build_ref(&self, user_ptr: Option<u64>) -> Vec<u8> {
+        let mut buf = vec![0; self.total_size()];
+        let user_ptr = user_ptr.unwrap_or(buf.as_ptr() as u64);
+        unsafe {
+            self.serialize_into_buf(&mut buf, user_ptr);
+        }
+        buf
+    }
+
+    /// Fallible version of [`Self::build_ref`].
+    ///
+    /// # Errors
+    /// Returns [`BuildError::MisalignedStackPointer`] instead of panicking, if the stack
+    /// pointer violates the alignment that [`Self::abi_aligned`] requires.
+    pub fn try_build_ref(&self, user_ptr: Option<u64>) -> Result<Vec<u8>, BuildError> {
+        let mut buf = vec![0; self.total_size()];
+        let user_ptr = user_ptr.unwrap_or(buf.as_ptr() as u64);
+        self.check_abi_alignment(user_ptr)?;
+        unsafe {
+            self.serialize_into_buf(&mut buf, user_ptr);
+        }
+        Ok(buf)
+    }
+
+    /// Like [`Self::build_ref`], but serializes into `buf`, which is resized to
+    /// [`Self::total_size`] bytes and zeroed. The capacity of `buf` is reused, so that
+    /// building many data structures in a loop doesn't need an allocation per iteration.
+    /// `buf` is only reallocated, if it is too small.
+    ///
+    /// # Parameters
+    /// * `buf`: Buffer that is overwritten.
+    /// * `user_ptr`: See [`Self::build_ref`].
+    ///
+    /// # Panics
+    /// Panics, if the memory of `buf` is not aligned to 8 bytes. A `Vec<u8>` doesn't guarantee
+    /// this, but the global allocators of common platforms return 8-byte aligned memory.
+    pub fn serialize_into_reusing(&self, buf: &mut Vec<u8>, user_ptr: Option<u64>) {
+        buf.clear();
+        buf.resize(self.total_size(), 0);
+        assert_eq!(
+            buf.as_ptr().align_offset(align_of::<u64>()),
+            0,
+            "the buffer must be aligned to 8 bytes!"
+        );
+        let user_ptr = user_ptr.unwrap_or(buf.as_ptr() as u64);
+        unsafe {
+            self.serialize_into_buf(buf, user_ptr);
+        }
+    }
+
+    /// Like [`Self::build_ref`], but prepends an 8-byte little-endian header with the length of
+    /// the data structure. This is useful to send the data structure over a channel, where
+    /// the receiver doesn't know the length. Use
+    /// [`crate::InitialLinuxLibcStackLayout::from_framed`] to parse it.
+    ///
+    /// # Parameters
+    /// * `user_ptr`: Stack pointer in user address space, i.e., the address of the data
+    ///   structure without the header. If `None`, the address of the data structure in the
+    ///   allocated buffer is used.
+    pub fn build_framed(&self, user_ptr: Option<u64>) -> Vec<u8> {
+        const HEADER_SIZE: usize = size_of::<u64>();
+        let mut buf = vec![0; HEADER_SIZE + self.total_size()];
+        buf[..HEADER_SIZE].copy_from_slice(&(self.total_size() as u64).to_le_bytes());
+        let payload = &mut buf[HEADER_SIZE..];
+        let user_ptr = user_ptr.unwrap_or(payload.as_ptr() as u64);
+        unsafe {
+            self.serialize_into_buf(payload, user_ptr);
+        }
+        buf
+    }
+
+    /// Allocates a zeroed buffer of [`Self::total_size`] bytes with the given allocator and
+    /// serializes the data structure into it.
+    ///
+    /// # Parameters
+    /// * `alloc`: Allocator for the buffer, e.g., an arena.
+    /// * `user_ptr`: Stack pointer in user address space. If `None`, the address of the
+    ///               allocated buffer is used, i.e., the structure is valid in the address space
+    ///               of the caller.
+    #[cfg(feature = "allocator_api")]
+    pub fn build_in<A: core::alloc::Allocator>(
+        &self,
+        alloc: A,
+        user_ptr: Option<u64>,
+    ) -> alloc::boxed::Box<[u8], A> {
+        let mut buf = Vec::with_capacity_in(self.total_size(), alloc);
+        buf.resize(self.total_size(), 0);
+        let mut buf = buf.into_boxed_slice();
+        let user_ptr = user_ptr.unwrap_or(buf.as_ptr() as u64);
+        unsafe {
+            self.serialize_into_buf(&mut buf, user_ptr);
+        }
+        buf
+    }
+
+    /// Checks that all addresses of the data structure fit into a word of the target, if it
+    /// is placed at `user_ptr` in the user address space. Pointers that don't fit are
+    /// truncated, for example, if a layout for a 32-bit target is created on a 64-bit host.
+    pub fn validate_address_range(
+        &self,
+        user_ptr: u64,
+        word_size: WordSize,
+    ) -> Result<(), BuildError> {
+        let max = match word_size {
+            WordSize::Bits32 => u64::from(u32::MAX),
+            WordSize::Bits64 => u64::MAX,
+        };
+        let overflow = |addr| BuildError::AddressOverflow { addr, word_size };
+        // last byte of the data structure
+        let end = user_ptr
+            .checked_add(self.total_size() as u64 - 1)
+            .ok_or_else(|| overflow(u64::MAX))?;
+        if end > max {
+            Err(overflow(end))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks the alignment of `user_ptr`, i.e., the address of `argc`, if
+    /// [`Self::abi_aligned`] is active.
+    pub const fn check_abi_alignment(&self, user_ptr: u64) -> Result<(), BuildError> {
+        if self.abi_aligned && user_ptr & 0xf != 0 {
+            Err(BuildError::MisalignedStackPointer(user_ptr))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks that not both [`AuxVarType::ExecFd`] and a non-default [`AuxVarType::ExecFn`]
+    /// are set. Linux loads a program either from a path or from a file descriptor, hence,
+    /// both entries are contradictory.
+    pub fn check_exec_exclusivity(&self) -> Result<(), LayoutError> {
+        let has_exec_fd = self.aux_v.iter().any(|x| x.key() == AuxVarType::ExecFd);
+        let has_exec_fn = matches!(
+            self.filename().and_then(AuxVar::value_payload_cstr),
+            Some(filename) if !matches!(filename, "" | "\0")
+        );
+        if has_exec_fd && has_exec_fn {
+            Err(LayoutError::ConflictingExec)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Serializes the data structure into two separate buffers. The first one contains the
+    /// entries (`argc`, `argv`, `envv`, and the auxiliary vector) and the second one contains
+    /// the data areas, i.e., the C-strings of the args and env vars and the referenced aux data.
+    /// This is useful, if the data areas must live in a separately mapped region in the user
+    /// address space.
+    ///
+    /// The entries can be placed anywhere in user address space, because no pointer references
+    /// them. The first buffer has a size that is a multiple of 16 bytes.
+    ///
+    /// # Parameters
+    /// * `data_user_ptr`: Address of the data areas (the second buffer) in user address space.
+    pub fn serialize_split(&self, data_user_ptr: u64) -> (Vec<u8>, Vec<u8>) {
+        let mut entries = vec![0; self.total_size()];
+        unsafe {
+            let write_ptr = entries.as_mut_ptr();
+            self.serialize(AuxvSerializer::new_split(self, write_ptr, data_user_ptr));
+        }
+        let data = entries.split_off(self.offset_to_aux_data_area());
+        (entries, data)
+    }
+
+    /// Like [`Self::serialize_split`] but pads both buffers to a multiple of `pagesz`, e.g.,
+    /// the value of [`AuxVarType::Pagesz`]. This way, both buffers can be mapped as separate
+    /// pages in user address space, for example with a guard page in-between.
+    ///
+    /// # Parameters
+    /// * `entries_addr`: Address of the entries (the first buffer) in user address space.
+    /// * `data_addr`: Address of the data areas (the second buffer) in user address space.
+    /// * `pagesz`: Page size of the target.
+    ///
+    /// # Panics
+    /// Panics, if `pagesz` is not a power of two or if an address is not page-aligned.
+    pub fn build_paged(
+        &self,
+        entries_addr: u64,
+        data_addr: u64,
+        pagesz: usize,
+    ) -> (Vec<u8>, Vec<u8>) {
+        assert!(pagesz.is_power_of_two(), "pagesz must be a power of two!");
+        let page_mask = pagesz as u64 - 1;
+        assert_eq!(
+            entries_addr & page_mask,
+            0,
+            "entries_addr must be page-aligned!"
+        );
+        assert_eq!(data_addr & page_mask, 0, "data_addr must be page-aligned!");
+        let (mut entries, mut data) = self.serialize_split(data_addr);
+        let page_align = |len: usize| (len + pagesz - 1) & !(pagesz - 1);
+        entries.resize(page_align(entries.len()), 0);
+        data.resize(page_align(data.len()), 0);
+        (entries, data)
+    }
+
+    /// Performs the serialization with the given serializer, that knows the write locations.
+    unsafe fn serialize(&self, mut writer: AuxvSerializer) {
         writer.write_argc(self.arg_v.len() as u64);
         for arg in &self.arg_v {
             writer.write_arg(arg);
@@ -93,6 +406,9 @@ impl<'a> InitialLinuxLibcStackLayoutBuilder<'a> {
         for aux in &self.aux_v {
             writer.write_aux_entry(aux)
         }
+        if self.trailing_sentinel {
+            writer.write_trailing_sentinel();
+        }
 
         writer.write_finish();
     }
@@ -128,6 +444,40 @@ impl<'a> InitialLinuxLibcStackLayoutBuilder<'a> {
         self
     }
 
+    /// Like [`Self::add_env_v`], but validates the format `KEY=VALUE` and returns an error
+    /// instead of panicking. The key must not be empty.
+    pub fn try_add_env_v(self, c_str: &'a str) -> Result<Self, EnvSyntaxError<'a>> {
+        if !cstr_contains_at_most_terminating_null_byte(c_str.as_bytes()) {
+            return Err(EnvSyntaxError::InteriorNul(c_str));
+        }
+        match c_str.find('=') {
+            None => Err(EnvSyntaxError::MissingEquals(c_str)),
+            Some(0) => Err(EnvSyntaxError::EmptyKey(c_str)),
+            Some(_) => Ok(self.add_env_v(c_str)),
+        }
+    }
+
+    /// Removes environment variables whose key (the part before the first `=`) was already
+    /// added before. Hence, the first occurrence of each key is kept, as most libc
+    /// implementations only see the first one anyway.
+    pub fn dedup_envv_keep_first(mut self) -> Self {
+        let key = |env: &'a str| env.split('=').next().unwrap_or(env);
+        let mut seen = BTreeSet::new();
+        self.env_v.retain(|env| seen.insert(key(env)));
+        self
+    }
+
+    /// Like [`Self::add_aux_v`], but ignores [`AuxVar::Null`]. This is useful to copy the
+    /// entries of a parsed data structure, which include the terminating entry. The builder
+    /// always contains exactly one terminating entry, which the serializer writes last.
+    pub fn add_aux_v_skip_null(self, var: AuxVar<'a>) -> Self {
+        if var.key() == AuxVarType::Null {
+            self
+        } else {
+            self.add_aux_v(var)
+        }
+    }
+
     /// Adds an aux entry.
     ///
     /// # Parameters
@@ -154,17 +504,342 @@ impl<'a> InitialLinuxLibcStackLayoutBuilder<'a> {
         self
     }
 
+    /// Adds the entries of an auxiliary vector that is given as flat list of raw
+    /// `(key, value)`-pairs, e.g., from `/proc/self/auxv`. Entries with an immediate value are
+    /// added directly. For entries that reference data in the aux vector data area, the data is
+    /// fetched with the `resolve` closure, which maps the raw value (a pointer in the source
+    /// address space) to the referenced bytes. C-strings may or may not include the null byte.
+    /// The import stops at [`AuxVarType::Null`].
+    pub fn aux_v_from_raw_pairs(
+        mut self,
+        pairs: &[(usize, usize)],
+        resolve: impl Fn(usize) -> Option<&'a [u8]>,
+    ) -> Result<Self, LayoutError> {
+        for &(key, val) in pairs {
+            let key = AuxVarType::try_from(key)?;
+            if key == AuxVarType::Null {
+                break;
+            }
+            let var = match AuxVar::from_immediate(key, val) {
+                Some(var) => var,
+                None => resolve(val)
+                    .and_then(|bytes| AuxVar::from_data_area_bytes(key, bytes))
+                    .ok_or(LayoutError::InvalidAuxData(key))?,
+            };
+            self = self.add_aux_v(var);
+        }
+        Ok(self)
+    }
+
+    /// Adds aux entries with an immediate value, i.e., types whose value doesn't live in the
+    /// aux data area (see [`AuxVarType::value_in_data_area`]), from `(type, value)`-pairs.
+    /// This is useful for integer values that come from a configuration.
+    ///
+    /// # Errors
+    /// Returns an error for types with referenced data and for [`AuxVarType::Null`], which
+    /// is always present. The unchanged builder is returned together with the error, i.e.,
+    /// no entry of `iter` is added in this case.
+    pub fn auxv_immediate_from_iter(
+        mut self,
+        iter: impl IntoIterator<Item = (AuxVarType, usize)>,
+    ) -> Result<Self, (Self, TypeMismatchError)> {
+        let pairs = iter.into_iter().collect::<Vec<_>>();
+        if let Some((key, _)) = pairs
+            .iter()
+            .find(|(key, _)| key.value_in_data_area() || *key == AuxVarType::Null)
+        {
+            return Err((self, TypeMismatchError(*key)));
+        }
+        for (key, val) in pairs {
+            let var = AuxVar::from_immediate(key, val).expect("checked above");
+            self = self.add_aux_v(var);
+        }
+        Ok(self)
+    }
+
+    /// Sets [`AuxVarType::Base`] to the load base of the program interpreter, i.e., the dynamic
+    /// linker (for example `ld-linux-x86-64.so.2`). This is not the load address of the main
+    /// program. Loaders usually know this value independently from the program headers of the
+    /// main program. For statically linked programs, this should be `0`.
+    pub fn with_interp_base(self, interp_base: usize) -> Self {
+        self.add_aux_v(AuxVar::Base(interp_base as *const u8))
+    }
+
+    /// Sets [`AuxVarType::SysinfoEhdr`] to the address of the ELF header of the vDSO.
+    pub fn vdso(self, ehdr_base: usize) -> Self {
+        self.add_aux_v(AuxVar::SysinfoEhdr(ehdr_base as *const u8))
+    }
+
+    /// Like [`Self::vdso`], but additionally sets [`AuxVarType::Sysinfo`] to the entry point of
+    /// `__kernel_vsyscall`, which is at `vsyscall_offset` in the vDSO. Only 32-bit x86
+    /// uses [`AuxVarType::Sysinfo`] to make system calls; other architectures, including
+    /// x86_64, don't set it.
+    pub fn vdso_with_vsyscall(self, ehdr_base: usize, vsyscall_offset: usize) -> Self {
+        self.vdso(ehdr_base)
+            .add_aux_v(AuxVar::Sysinfo((ehdr_base + vsyscall_offset) as *const u8))
+    }
+
+    /// Sets [`AuxVarType::Secure`] like Linux does for setuid/setgid programs, i.e., to true
+    /// if the real and the effective user ID or the real and the effective group ID differ.
+    /// Therefore, [`AuxVarType::Uid`], [`AuxVarType::EUid`], [`AuxVarType::Gid`], and
+    /// [`AuxVarType::EGid`] must be added before. A pair of IDs with a missing entry is
+    /// treated as equal.
+    pub fn compute_secure(self) -> Self {
+        let id = |typ| {
+            self.aux_v
+                .iter()
+                .find(|x| x.key() == typ)
+                .map(AuxVar::value_raw)
+        };
+        let differ = |real, effective| match (id(real), id(effective)) {
+            (Some(real), Some(effective)) => real != effective,
+            _ => false,
+        };
+        let secure =
+            differ(AuxVarType::Uid, AuxVarType::EUid) || differ(AuxVarType::Gid, AuxVarType::EGid);
+        self.add_aux_v(AuxVar::Secure(secure))
+    }
+
+    /// Adds the entries to the auxiliary vector, that the given libc relies on. See
+    /// [`LibcTarget`] for the specific adjustments. Existing entries are kept. Added defaults
+    /// are a page size of 4096 and 100 clock ticks per second.
+    pub fn libc_target(self, target: LibcTarget) -> Self {
+        match target {
+            LibcTarget::Generic => self,
+            LibcTarget::Glibc { random } => self.add_aux_v_if_absent(AuxVar::Random(random)),
+            LibcTarget::Musl => self
+                .add_aux_v_if_absent(AuxVar::Pagesz(4096))
+                .add_aux_v_if_absent(AuxVar::Clktck(100)),
+        }
+    }
+
+    /// Like [`Self::add_aux_v`], but keeps an existing entry of the same type.
+    fn add_aux_v_if_absent(self, var: AuxVar<'a>) -> Self {
+        if self.aux_v.contains(&var) {
+            self
+        } else {
+            self.add_aux_v(var)
+        }
+    }
+
+    /// Sets [`AuxVarType::Phdr`], [`AuxVarType::Phent`], and [`AuxVarType::Phnum`] from the
+    /// values of an ELF header, which the caller already parsed. The program headers are
+    /// expected at `load_base + e_phoff`, i.e., the beginning of the ELF file must be mapped at
+    /// `load_base`.
+    ///
+    /// # Errors
+    /// Returns [`BuildError::AddressOverflow`], if `load_base + e_phoff` doesn't fit into a
+    /// `usize`.
+    pub fn set_phdr_from(
+        self,
+        load_base: usize,
+        e_phoff: u64,
+        e_phentsize: u16,
+        e_phnum: u16,
+    ) -> Result<Self, BuildError> {
+        let phdr = usize::try_from(e_phoff)
+            .ok()
+            .and_then(|e_phoff| load_base.checked_add(e_phoff))
+            .ok_or_else(|| BuildError::AddressOverflow {
+                addr: (load_base as u64).saturating_add(e_phoff),
+                word_size: if size_of::<usize>() == 4 {
+                    WordSize::Bits32
+                } else {
+                    WordSize::Bits64
+                },
+            })?;
+        Ok(self
+            .add_aux_v(AuxVar::Phdr(phdr as *const u8))
+            .add_aux_v(AuxVar::Phent(usize::from(e_phentsize)))
+            .add_aux_v(AuxVar::Phnum(usize::from(e_phnum))))
+    }
+
+    /// Sets [`AuxVarType::MinSigStkSz`] to the value that the host kernel reports via
+    /// `getauxval(AT_MINSIGSTKSZ)`. If the host doesn't report it (kernels before 5.14 or
+    /// architectures that don't support it), [`Self::DEFAULT_MINSIGSTKSZ`] is used.
+    #[cfg(all(feature = "std", target_os = "linux"))]
+    pub fn minsigstksz_from_host(self) -> Self {
+        extern "C" {
+            fn getauxval(typ: std::os::raw::c_ulong) -> std::os::raw::c_ulong;
+        }
+        let val = unsafe { getauxval(AuxVarType::MinSigStkSz.val() as _) } as usize;
+        let val = if val == 0 {
+            Self::DEFAULT_MINSIGSTKSZ
+        } else {
+            val
+        };
+        self.add_aux_v(AuxVar::MinSigStkSz(val))
+    }
+
+    /// Adds the arguments and the environment variables of the process with the given PID, as
+    /// found in `/proc/<pid>/cmdline` and `/proc/<pid>/environ`. Empty entries are kept. Both
+    /// files are read into `buf`, which the builder borrows from. The previous content of `buf`
+    /// is replaced.
+    ///
+    /// # Errors
+    /// Returns an error, if the files can't be read or if they are not valid UTF-8.
+    #[cfg(all(feature = "std", target_os = "linux"))]
+    pub fn argv_envv_from_pid(
+        mut self,
+        pid: u32,
+        buf: &'a mut std::string::String,
+    ) -> std::io::Result<Self> {
+        use std::io::Read;
+        buf.clear();
+        let mut read = |file: &str| -> std::io::Result<usize> {
+            std::fs::File::open(std::format!("/proc/{}/{}", pid, file))?.read_to_string(buf)
+        };
+        let cmdline_len = read("cmdline")?;
+        read("environ")?;
+        let buf: &'a str = buf;
+        // all entries are null-terminated, even the last one
+        let entries = |content: &'a str| {
+            content
+                .strip_suffix('\0')
+                .into_iter()
+                .flat_map(|content| content.split('\0'))
+        };
+        for arg in entries(&buf[..cmdline_len]) {
+            self = self.add_arg_v(arg);
+        }
+        for env in entries(&buf[cmdline_len..]) {
+            self = self.add_env_v(env);
+        }
+        Ok(self)
+    }
+
+    /// Adds an environment variable `KEY=VALUE` for each entry of `map`. The entries are added
+    /// in the order of their keys, so that builds from the same map are reproducible. The
+    /// formatted entries are stored in `storage`, which the builder borrows from. The previous
+    /// content of `storage` is replaced.
+    #[cfg(feature = "std")]
+    pub fn envv_from_map(
+        mut self,
+        map: &std::collections::HashMap<std::string::String, std::string::String>,
+        storage: &'a mut Vec<std::string::String>,
+    ) -> Self {
+        let mut entries = map.iter().collect::<Vec<_>>();
+        entries.sort_unstable();
+        storage.clear();
+        storage.extend(
+            entries
+                .into_iter()
+                .map(|(key, value)| std::format!("{}={}", key, value)),
+        );
+        for env in storage.iter() {
+            self = self.add_env_v(env);
+        }
+        self
+    }
+
+    /// Pads the data structure, so that the aux data area begins at a multiple of `pagesz`,
+    /// e.g., the value of [`AuxVarType::Pagesz`]. The offset is relative to the beginning of the
+    /// data structure. Hence, the user address must be page-aligned as well, to get a
+    /// page-aligned data area in the user address space.
+    ///
+    /// # Panics
+    /// Panics, if `pagesz` is not a power of two.
+    pub fn page_align_data(mut self, pagesz: usize) -> Self {
+        assert!(pagesz.is_power_of_two(), "pagesz must be a power of two!");
+        self.data_area_alignment = Some(pagesz);
+        self
+    }
+
+    /// Requires that `argc` is placed at a 16-byte aligned address, as the x86_64 System V
+    /// ABI requires it for the stack pointer at process entry. The structure itself can't be
+    /// shifted, because the stack pointer points to `argc`. Hence, serializing to a
+    /// misaligned address panics. This also applies to the address of the buffer, if no
+    /// `user_ptr` is given to [`Self::build_ref`]. Use [`Self::check_abi_alignment`] to check
+    /// the address before or [`Self::try_build_ref`] to get an error instead.
+    pub const fn abi_aligned(mut self, abi_aligned: bool) -> Self {
+        self.abi_aligned = abi_aligned;
+        self
+    }
+
+    /// Appends trailing zero padding, so that [`Self::total_size`] equals `total_len`. This
+    /// is useful to byte-compare the data structure with a capture of the same size, e.g.,
+    /// from Linux. Fails, if the content is already bigger than `total_len`. Entries that are
+    /// added afterwards may again exceed `total_len`.
+    pub fn pad_to(mut self, total_len: usize) -> Result<Self, BuildError> {
+        let required = self.content_size();
+        if required > total_len {
+            return Err(BuildError::TooLarge {
+                required,
+                limit: total_len,
+            });
+        }
+        self.padded_size = Some(total_len);
+        Ok(self)
+    }
+
+    /// Sets the location of the C-string of [`AuxVarType::ExecFn`]. Parsers that expect the
+    /// placement of Linux need [`ExecFnPlacement::TopOfStack`], which is the default.
+    pub const fn execfn_placement(mut self, placement: ExecFnPlacement) -> Self {
+        self.execfn_placement = placement;
+        self
+    }
+
+    /// Appends an additional zero word after the terminating [`AuxVarType::Null`] entry of
+    /// the auxiliary vector. Some runtimes expect it, because the setup of Linux leaves one
+    /// there. Parsers stop at the terminating entry, hence, the word is invisible to them.
+    pub const fn emit_trailing_sentinel(mut self, emit: bool) -> Self {
+        self.trailing_sentinel = emit;
+        self
+    }
+
+    /// Lets the serializer write identical arguments or identical environment variables only
+    /// once into the data area, so that multiple entries of `argv` respectively `envv` point
+    /// to one shared copy. This saves space for space-constrained targets. A libc doesn't
+    /// write to these strings, so shared storage is fine. However, the data pointers are no
+    /// longer strictly ascending.
+    pub const fn dedup_strings(mut self, dedup: bool) -> Self {
+        self.dedup_strings = dedup;
+        self
+    }
+
+    /// Removes all aux entries that belong to the given [`AuxVarCategory`]. This is useful to
+    /// reuse a builder as template for a different target, e.g., by removing all
+    /// [`AuxVarCategory::Cpu`] entries. The terminating [`AuxVarType::Null`] entry is never
+    /// removed.
+    pub fn remove_category(mut self, category: AuxVarCategory) -> Self {
+        self.aux_v
+            .retain(|aux| aux.key() == AuxVarType::Null || aux.key().category() != category);
+        self
+    }
+
     /// Returns the number in bytes the data structure will have including the final
     /// null byte.
     pub fn total_size(&self) -> usize {
+        self.padded_size.unwrap_or(0).max(self.content_size())
+    }
+
+    /// Returns the difference of [`Self::total_size`] from `self` to `other` in bytes. This
+    /// is useful to show how many bytes an additional entry costs, including the effects of
+    /// alignment.
+    pub fn size_delta(&self, other: &InitialLinuxLibcStackLayoutBuilder<'_>) -> isize {
+        other.total_size() as isize - self.total_size() as isize
+    }
+
+    /// Returns the number in bytes the data structure will have without the trailing padding
+    /// of [`Self::pad_to`].
+    fn content_size(&self) -> usize {
         // final null is 64 byte long
         self.offset_to_final_null() + size_of::<u64>()
     }
 
+    /// Returns the number of padding bytes that are inserted to align the data areas to a
+    /// 16 byte boundary.
+    pub fn alignment_padding(&self) -> AlignmentPadding {
+        AlignmentPadding {
+            before_aux_data: self.offset_to_aux_data_area() - self.end_of_aux_key_area(),
+            before_argv_data: self.offset_to_argv_data_area() - self.end_of_aux_data_area(),
+        }
+    }
+
     /// Returns the total offset from the begin pointer to the aux data area.
     const fn offset_to_argv_key_area(&self) -> usize {
         // there is only argc before this
-        size_of::<u64>()
+        layout_math::ARGC_SIZE
     }
 
     /// Returns the total offset from the begin pointer to the aux data area.
@@ -179,28 +854,38 @@ impl<'a> InitialLinuxLibcStackLayoutBuilder<'a> {
 
     /// Returns the total offset from the begin pointer to the aux data area.
     fn offset_to_aux_data_area(&self) -> usize {
-        let mut sum = self.offset_to_aux_key_area() + self.aux_keys_size();
+        let sum = self.end_of_aux_key_area();
 
         // TODO seems like Linux does some more magic for stack alignment
         //  https://elixir.bootlin.com/linux/v5.15.5/source/fs/binfmt_elf.c#L200
         //  Maybe solve this in the future?! IMHO this looks negligible.
         //  Some L1 Cache optimizations on x86_64
 
-        // align up to next 16 byte boundary
-        if sum % 16 != 0 {
-            sum += 16 - sum % 16;
-        }
-        sum
+        // align up to next 16 byte boundary (or to the requested bigger alignment)
+        let alignment = self
+            .data_area_alignment
+            .unwrap_or(layout_math::DATA_AREA_ALIGNMENT)
+            .max(layout_math::DATA_AREA_ALIGNMENT);
+        layout_math::align_up(sum, alignment)
     }
 
     /// Returns the total offset from the begin pointer to the args data area.
     fn offset_to_argv_data_area(&self) -> usize {
-        let mut sum = self.offset_to_aux_data_area() + self.aux_data_area_size();
         // align up to next 16 byte boundary
-        if sum % 16 != 0 {
-            sum += 16 - sum % 16;
-        }
-        sum
+        layout_math::align_up(
+            self.end_of_aux_data_area(),
+            layout_math::DATA_AREA_ALIGNMENT,
+        )
+    }
+
+    /// Returns the offset of the first byte after the aux key area (without padding).
+    fn end_of_aux_key_area(&self) -> usize {
+        self.offset_to_aux_key_area() + self.aux_keys_size()
+    }
+
+    /// Returns the offset of the first byte after the aux data area (without padding).
+    fn end_of_aux_data_area(&self) -> usize {
+        self.offset_to_aux_data_area() + self.aux_data_area_size()
     }
 
     /// Returns the total offset from the begin pointer to the env data area.
@@ -213,11 +898,14 @@ impl<'a> InitialLinuxLibcStackLayoutBuilder<'a> {
         self.offset_to_env_data_area() + self.envv_data_area_size()
     }
 
-    /// Returns the total offset from the begin pointer to the final null (u64).
+    /// Returns the total offset from the begin pointer to the final null (u64). This null word
+    /// marks the top of the structure, as the final word at the top of the stack does in the
+    /// layout that Linux creates (see the figure in the crate documentation). Code that walks
+    /// the structure from the file name upwards, finds the end there.
     fn offset_to_final_null(&self) -> usize {
         // bytes for the filename C-string including the final null byte
         let filename_bytes = self
-            .filename()
+            .filename_at_top()
             .map(|aux| cstr_len_with_nullbyte(aux.value_payload_cstr().unwrap().as_bytes()))
             .unwrap_or(0);
         self.offset_to_filename_data_area() + filename_bytes
@@ -226,41 +914,63 @@ impl<'a> InitialLinuxLibcStackLayoutBuilder<'a> {
     /// Returns the number in bytes that all argv entries will occupy.
     /// Only the entries, but not the referenced data.
     fn argv_keys_size(&self) -> usize {
-        // +1: null terminated
-        size_of::<u64>() * (self.arg_v.len() + 1)
+        layout_math::argv_entries_len(self.arg_v.len())
     }
 
     /// Returns the number in bytes that all env entries will occupy.
     /// Only the entries, but not the referenced data.
     fn envv_keys_size(&self) -> usize {
-        // +1: null terminated
-        size_of::<u64>() * (self.env_v.len() + 1)
+        layout_math::envv_entries_len(self.env_v.len())
     }
 
     /// Returns the number in bytes that all AT entries will occupy.
     /// Only the entries, but not the referenced data.
     fn aux_keys_size(&self) -> usize {
-        size_of::<AuxVarSerialized>() * self.aux_v.len()
+        let sentinel_size = if self.trailing_sentinel {
+            size_of::<usize>()
+        } else {
+            0
+        };
+        layout_math::auxv_entries_len(self.aux_v.len(), layout_math::NATIVE_WORD_SIZE)
+            + sentinel_size
     }
 
     /// Returns the sum of bytes, required to store the C-string of each arg, including
-    /// terminating null bytes.
-    fn argv_data_area_size(&self) -> usize {
-        self.arg_v
-            .iter()
-            .map(|x| cstr_len_with_nullbyte(x.as_bytes()))
-            .sum()
+    /// terminating null bytes. Useful to size external string tables.
+    pub fn argv_data_area_size(&self) -> usize {
+        self.strings_data_area_size(&self.arg_v)
     }
 
     /// Returns the sum of bytes, required to store the C-string of each env var, including
-    /// terminating null bytes.
-    fn envv_data_area_size(&self) -> usize {
-        self.env_v
-            .iter()
-            .map(|x| cstr_len_with_nullbyte(x.as_bytes()))
+    /// terminating null bytes. Useful to size external string tables.
+    pub fn envv_data_area_size(&self) -> usize {
+        self.strings_data_area_size(&self.env_v)
+    }
+
+    /// Returns the sum of bytes, required to store the given C-strings in the data area.
+    /// Strings that share the copy of a previous string (see [`Self::shared_string_index`])
+    /// don't occupy extra bytes.
+    fn strings_data_area_size(&self, strings: &[&str]) -> usize {
+        (0..strings.len())
+            .filter(|i| self.shared_string_index(strings, *i).is_none())
+            .map(|i| cstr_len_with_nullbyte(strings[i].as_bytes()))
             .sum()
     }
 
+    /// Returns the index of the first previous string that is identical to `strings[index]`,
+    /// if [`Self::dedup_strings`] is active. The optional terminating null byte is ignored.
+    fn shared_string_index(&self, strings: &[&str], index: usize) -> Option<usize> {
+        if !self.dedup_strings {
+            return None;
+        }
+        fn trim(x: &str) -> &str {
+            x.strip_suffix('\0').unwrap_or(x)
+        }
+        strings[..index]
+            .iter()
+            .position(|x| trim(x) == trim(strings[index]))
+    }
+
     /// Returns the number of all additional aux vec data in the aux data area, except for
     /// the executable name of [`AuxVarType::AtExecFn`], because it gets special treatment.
     ///
@@ -269,9 +979,11 @@ impl<'a> InitialLinuxLibcStackLayoutBuilder<'a> {
         self.aux_v
             .iter()
             .filter(|x| x.key().value_in_data_area())
-            // AtExecFn: file name stands at end of the structure, before the final null byte
-            //           and not in the auxv data area
-            .filter(|x| x.key() != AuxVarType::ExecFn)
+            // AtExecFn: file name usually stands at end of the structure, before the final null
+            //           byte and not in the auxv data area
+            .filter(|x| {
+                x.key() != AuxVarType::ExecFn || self.execfn_placement == ExecFnPlacement::DataArea
+            })
             // for convenience reasons, users can enter string slices without terminating
             // null byte - take care here manually!
             .map(|aux| aux.data_area_serialize_byte_count())
@@ -286,12 +998,19 @@ impl<'a> InitialLinuxLibcStackLayoutBuilder<'a> {
     fn filename(&self) -> Option<&AuxVar> {
         self.aux_v.iter().find(|x| x.key() == AuxVarType::ExecFn)
     }
+
+    /// Returns the filename/executable aux var, if it is placed at the top of the structure.
+    /// See [`Self::execfn_placement`].
+    fn filename_at_top(&self) -> Option<&AuxVar<'_>> {
+        self.filename()
+            .filter(|_| self.execfn_placement == ExecFnPlacement::TopOfStack)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::AuxVarType;
+    use crate::{AuxVarSerialized, AuxVarType, InitialLinuxLibcStackLayout};
 
     #[test]
     fn test_builder_write_size() {
@@ -428,4 +1147,910 @@ mod tests {
         let actual = b.filename().unwrap().value_payload_cstr().unwrap();
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_with_interp_base() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new().with_interp_base(0x7f00_0000);
+        let mut buf = vec![0; builder.total_size()];
+        unsafe {
+            builder.serialize_into_buf(&mut buf, 0x1000);
+        }
+
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let base = parsed
+            .aux_serialized_iter()
+            .find(|aux| aux.key() == AuxVarType::Base)
+            .unwrap();
+        assert_eq!(base.val(), 0x7f00_0000);
+    }
+
+    #[test]
+    fn test_remove_category() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_aux_v(AuxVar::HwCap(0x1000))
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::L1dCacheSize(0x8000))
+            .add_aux_v(AuxVar::Uid(1000))
+            .add_aux_v(AuxVar::Pagesz(4096))
+            .remove_category(AuxVarCategory::Cpu);
+
+        let keys = builder.aux_v.iter().map(AuxVar::key).collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                AuxVarType::Pagesz,
+                AuxVarType::Uid,
+                AuxVarType::ExecFn,
+                AuxVarType::Null
+            ]
+        );
+
+        let builder = builder.remove_category(AuxVarCategory::Terminator);
+        assert_eq!(builder.aux_v.iter().last().unwrap().key(), AuxVarType::Null);
+    }
+
+    #[test]
+    fn test_serialize_split() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_arg_v("second_arg")
+            .add_env_v("ENV1=FOO")
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::ExecFn("./executable"));
+
+        // reassemble both parts in one buffer with a gap of 64 bytes in-between
+        let mut buf = vec![0_u8; builder.total_size() + 64];
+        let data_offset = builder.offset_to_aux_data_area() + 64;
+        let (entries, data) = builder.serialize_split(buf[data_offset..].as_ptr() as u64);
+        assert_eq!(entries.len() % 16, 0);
+        assert_eq!(entries.len() + data.len(), builder.total_size());
+        buf[..entries.len()].copy_from_slice(&entries);
+        buf[data_offset..].copy_from_slice(&data);
+
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        unsafe {
+            assert_eq!(
+                parsed.argv_iter().collect::<Vec<_>>(),
+                ["first_arg\0", "second_arg\0"]
+            );
+            assert_eq!(parsed.envv_iter().collect::<Vec<_>>(), ["ENV1=FOO\0"]);
+            let aux = parsed.aux_var_iter().collect::<Vec<_>>();
+            assert_eq!(aux[0].value_payload_cstr(), Some("x86_64"));
+            assert_eq!(aux[1].value_payload_cstr(), Some("./executable"));
+        }
+    }
+
+    #[test]
+    fn test_argv_envv_data_area_size() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("./executable")
+            .add_arg_v("--foo\0")
+            .add_env_v("PATH=/bin")
+            .add_env_v("HOME=/root\0")
+            .add_env_v("FOO=BAR");
+
+        assert_eq!(builder.argv_data_area_size(), 12 + 1 + 5 + 1);
+        assert_eq!(builder.envv_data_area_size(), 9 + 1 + 10 + 1 + 7 + 1);
+        assert_eq!(
+            InitialLinuxLibcStackLayoutBuilder::new().argv_data_area_size(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_aux_v_from_raw_pairs() {
+        let platform = b"x86_64\0";
+        let random = [7; 16];
+        let resolve = |addr| match addr {
+            0x1000 => Some(&platform[..]),
+            0x2000 => Some(&random[..]),
+            _ => None,
+        };
+        let pairs = [
+            (AuxVarType::Pagesz.val(), 4096),
+            (AuxVarType::Platform.val(), 0x1000),
+            (AuxVarType::Random.val(), 0x2000),
+            (AuxVarType::Uid.val(), 1000),
+            (AuxVarType::Null.val(), 0),
+            // ignored, after AT_NULL
+            (AuxVarType::Gid.val(), 1000),
+        ];
+
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .aux_v_from_raw_pairs(&pairs, resolve)
+            .unwrap();
+        let aux = builder.aux_v.iter().collect::<Vec<_>>();
+        assert_eq!(aux.len(), 6);
+        assert_eq!(aux[0].value_integer(), Some(4096));
+        assert_eq!(aux[2].value_payload_cstr(), Some("x86_64"));
+        assert_eq!(aux[3].value_payload_bytes(), Some(&random[..]));
+        assert!(!builder.aux_v.contains(&AuxVar::Gid(0)));
+
+        let err = InitialLinuxLibcStackLayoutBuilder::new()
+            .aux_v_from_raw_pairs(&[(AuxVarType::ExecFn.val(), 0x3000)], resolve)
+            .unwrap_err();
+        assert_eq!(err, LayoutError::InvalidAuxData(AuxVarType::ExecFn));
+        let err = InitialLinuxLibcStackLayoutBuilder::new()
+            .aux_v_from_raw_pairs(&[(999, 0)], resolve)
+            .unwrap_err();
+        assert_eq!(err, LayoutError::UnknownAuxVarType(999));
+    }
+
+    #[test]
+    fn test_builder_writes_final_null() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("Foo")
+            .add_env_v("BAR=FOO")
+            .add_aux_v(AuxVar::ExecFn("./exe"));
+        // dirty buffer: the serializer must write the final null word by itself
+        let mut buf = vec![0xff; builder.total_size()];
+        unsafe {
+            let user_ptr = buf.as_ptr();
+            builder.serialize_into_buf(&mut buf, user_ptr as u64);
+        }
+        let final_null = &buf[buf.len() - size_of::<usize>()..];
+        assert!(final_null.iter().all(|b| *b == 0));
+        // the byte before is the null byte of the file name
+        assert_eq!(
+            &buf[buf.len() - size_of::<u64>() - 6..buf.len() - size_of::<u64>()],
+            b"./exe\0"
+        );
+    }
+
+    #[cfg(feature = "allocator_api")]
+    #[test]
+    fn test_build_in() {
+        use core::alloc::{AllocError, Allocator, Layout};
+        use core::cell::Cell;
+        use core::ptr::NonNull;
+        use std::alloc::Global;
+
+        /// Counts the allocations and forwards them to the global allocator.
+        #[derive(Debug, Default)]
+        struct TrackingAllocator {
+            allocations: Cell<usize>,
+        }
+
+        unsafe impl Allocator for &TrackingAllocator {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                self.allocations.set(self.allocations.get() + 1);
+                Global.allocate(layout)
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                Global.deallocate(ptr, layout)
+            }
+        }
+
+        let tracking_allocator = TrackingAllocator::default();
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("ENV1=FOO")
+            .add_aux_v(AuxVar::Platform("x86_64"));
+        let buf = builder.build_in(&tracking_allocator, None);
+        assert!(tracking_allocator.allocations.get() > 0);
+        assert_eq!(buf.len(), builder.total_size());
+
+        let parsed = InitialLinuxLibcStackLayout::from(&buf[..]);
+        assert_eq!(parsed.argc(), 1);
+        assert_eq!(unsafe { parsed.argv_iter() }.next(), Some("first_arg\0"));
+    }
+
+    #[test]
+    fn test_alignment_padding() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new();
+        // argc, argv null, envv null (3 * 8) and two aux entries
+        let expected = (24 + 2 * size_of::<AuxVarSerialized>()) % 16;
+        assert_eq!(
+            builder.alignment_padding(),
+            AlignmentPadding {
+                before_aux_data: (16 - expected) % 16,
+                before_argv_data: 0,
+            }
+        );
+
+        // 8 bytes of aux keys more than above, 7 bytes of aux data
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("Foo")
+            .add_aux_v(AuxVar::Platform("x86_64"));
+        let unaligned = 8 + 16 + 8 + 3 * size_of::<AuxVarSerialized>();
+        let padding = builder.alignment_padding();
+        assert_eq!(padding.before_aux_data, (16 - unaligned % 16) % 16);
+        assert_eq!(padding.before_argv_data, 16 - 7);
+        assert_eq!(
+            builder.offset_to_argv_data_area(),
+            unaligned + padding.before_aux_data + 7 + padding.before_argv_data
+        );
+    }
+
+    #[test]
+    fn test_build_ref() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("Foo")
+            .add_env_v("BAR=FOO")
+            .add_aux_v(AuxVar::Platform("x86_64"));
+        let first = builder.build_ref(Some(0x1000));
+        let second = builder.build_ref(Some(0x1000));
+        assert_eq!(first, second);
+
+        // the builder is still usable
+        let builder = builder.add_arg_v("Bar");
+        let third = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(third.as_slice());
+        assert_eq!(parsed.argc(), 2);
+        assert_eq!(unsafe { parsed.argv_iter() }.nth(1), Some("Bar\0"));
+    }
+
+    #[test]
+    fn test_page_align_data() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("Foo")
+            .add_env_v("BAR=FOO")
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::Pagesz(4096))
+            .page_align_data(4096);
+        assert_eq!(builder.offset_to_aux_data_area(), 4096);
+        assert_eq!(
+            builder.alignment_padding().before_aux_data,
+            4096 - builder.end_of_aux_key_area()
+        );
+
+        let buf = builder.build_ref(Some(0x7fff_0000));
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let platform = parsed
+            .aux_serialized_iter()
+            .find(|aux| aux.key() == AuxVarType::Platform)
+            .unwrap();
+        assert_eq!(platform.val(), 0x7fff_0000 + 4096);
+        assert_eq!(&buf[4096..4096 + 7], b"x86_64\0");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_page_align_data_power_of_two() {
+        let _ = InitialLinuxLibcStackLayoutBuilder::new().page_align_data(4000);
+    }
+
+    #[test]
+    fn test_set_phdr_from() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .set_phdr_from(0x40_0000, 64, 56, 13)
+            .unwrap();
+        let buf = builder.build_ref(Some(0x1000));
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let find = |key| {
+            parsed
+                .aux_serialized_iter()
+                .find(|aux| aux.key() == key)
+                .map(|aux| aux.val())
+        };
+        assert_eq!(find(AuxVarType::Phdr), Some(0x40_0040));
+        assert_eq!(find(AuxVarType::Phent), Some(56));
+        assert_eq!(find(AuxVarType::Phnum), Some(13));
+
+        assert!(matches!(
+            InitialLinuxLibcStackLayoutBuilder::new().set_phdr_from(usize::MAX, 64, 56, 13),
+            Err(BuildError::AddressOverflow { addr: u64::MAX, .. })
+        ));
+        assert!(InitialLinuxLibcStackLayoutBuilder::new()
+            .set_phdr_from(0x40_0000, u64::MAX, 56, 13)
+            .is_err());
+    }
+
+    #[cfg(all(feature = "std", target_os = "linux"))]
+    #[test]
+    fn test_minsigstksz_from_host() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new().minsigstksz_from_host();
+        let buf = builder.build_ref(Some(0x1000));
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let val = parsed
+            .aux_serialized_iter()
+            .find(|aux| aux.key() == AuxVarType::MinSigStkSz)
+            .map(|aux| aux.val());
+        assert!(matches!(val, Some(val) if val > 0));
+    }
+
+    #[test]
+    fn test_validate_address_range() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("Foo")
+            .add_aux_v(AuxVar::Platform("i686"));
+        let size = builder.total_size() as u64;
+        let max = u64::from(u32::MAX);
+
+        assert_eq!(
+            builder.validate_address_range(max + 1 - size, WordSize::Bits32),
+            Ok(())
+        );
+        assert_eq!(
+            builder.validate_address_range(max - 16, WordSize::Bits32),
+            Err(BuildError::AddressOverflow {
+                addr: max - 16 + size - 1,
+                word_size: WordSize::Bits32
+            })
+        );
+        assert_eq!(
+            builder.validate_address_range(max - 16, WordSize::Bits64),
+            Ok(())
+        );
+        assert!(builder
+            .validate_address_range(u64::MAX - 16, WordSize::Bits64)
+            .is_err());
+    }
+
+    #[cfg(all(feature = "std", target_os = "linux"))]
+    #[test]
+    fn test_argv_envv_from_pid() {
+        let mut buf = std::string::String::from("stale");
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .argv_envv_from_pid(std::process::id(), &mut buf)
+            .unwrap();
+        let args = std::env::args().collect::<Vec<_>>();
+        assert_eq!(builder.arg_v, args);
+        assert!(builder.env_v.iter().all(|env| !env.contains('\0')));
+
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(parsed.argc(), args.len());
+
+        let mut buf = std::string::String::new();
+        assert!(InitialLinuxLibcStackLayoutBuilder::new()
+            .argv_envv_from_pid(u32::MAX, &mut buf)
+            .is_err());
+    }
+
+    #[test]
+    fn test_dedup_envv_keep_first() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_env_v("FOO=1")
+            .add_env_v("BAR=1")
+            .add_env_v("FOO=2\0")
+            .add_env_v("FOOBAR=3")
+            .dedup_envv_keep_first();
+        assert_eq!(builder.env_v, ["FOO=1", "BAR=1", "FOOBAR=3"]);
+    }
+
+    #[test]
+    fn test_compute_secure() {
+        let secure = |builder: InitialLinuxLibcStackLayoutBuilder| {
+            builder
+                .compute_secure()
+                .aux_v
+                .iter()
+                .find(|x| x.key() == AuxVarType::Secure)
+                .and_then(AuxVar::value_boolean)
+        };
+        let builder = || {
+            InitialLinuxLibcStackLayoutBuilder::new()
+                .add_aux_v(AuxVar::Uid(1000))
+                .add_aux_v(AuxVar::Gid(1000))
+                .add_aux_v(AuxVar::EGid(1000))
+        };
+        assert_eq!(secure(builder().add_aux_v(AuxVar::EUid(0))), Some(true));
+        assert_eq!(secure(builder().add_aux_v(AuxVar::EUid(1000))), Some(false));
+        assert_eq!(
+            secure(
+                builder()
+                    .add_aux_v(AuxVar::EUid(1000))
+                    .add_aux_v(AuxVar::EGid(0))
+            ),
+            Some(true)
+        );
+        assert_eq!(secure(builder()), Some(false));
+    }
+
+    #[test]
+    fn test_auxv_immediate_from_iter() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .auxv_immediate_from_iter([
+                (AuxVarType::Pagesz, 4096),
+                (AuxVarType::Uid, 1000),
+                (AuxVarType::Secure, 1),
+            ])
+            .unwrap();
+        let find = |key| builder.aux_v.iter().find(|x| x.key() == key);
+        assert!(find(AuxVarType::Pagesz)
+            .unwrap()
+            .eq_content(&AuxVar::Pagesz(4096)));
+        assert!(find(AuxVarType::Uid)
+            .unwrap()
+            .eq_content(&AuxVar::Uid(1000)));
+        assert!(find(AuxVarType::Secure)
+            .unwrap()
+            .eq_content(&AuxVar::Secure(true)));
+
+        let (builder, err) = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .auxv_immediate_from_iter([(AuxVarType::Uid, 1000), (AuxVarType::Platform, 0x1000)])
+            .unwrap_err();
+        assert_eq!(err, TypeMismatchError(AuxVarType::Platform));
+        // the builder is returned unchanged
+        assert_eq!(builder.arg_v, ["first_arg"]);
+        assert!(!builder.aux_v.iter().any(|x| x.key() == AuxVarType::Uid));
+    }
+
+    #[test]
+    fn test_libc_target() {
+        let keys = |target| {
+            InitialLinuxLibcStackLayoutBuilder::new()
+                .libc_target(target)
+                .aux_v
+                .iter()
+                .map(AuxVar::key)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            keys(LibcTarget::Generic),
+            [AuxVarType::ExecFn, AuxVarType::Null]
+        );
+        assert_eq!(
+            keys(LibcTarget::Glibc { random: [7; 16] }),
+            [AuxVarType::Random, AuxVarType::ExecFn, AuxVarType::Null]
+        );
+        assert_eq!(
+            keys(LibcTarget::Musl),
+            [
+                AuxVarType::Pagesz,
+                AuxVarType::Clktck,
+                AuxVarType::ExecFn,
+                AuxVarType::Null
+            ]
+        );
+
+        // existing entries are kept
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_aux_v(AuxVar::Pagesz(0x200000))
+            .libc_target(LibcTarget::Musl);
+        let pagesz = builder.aux_v.iter().find(|x| x.key() == AuxVarType::Pagesz);
+        assert!(pagesz.unwrap().eq_content(&AuxVar::Pagesz(0x200000)));
+
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .libc_target(LibcTarget::Glibc { random: [7; 16] });
+        let random = builder.aux_v.iter().find(|x| x.key() == AuxVarType::Random);
+        assert!(random.unwrap().eq_content(&AuxVar::Random([7; 16])));
+    }
+
+    #[test]
+    fn test_serialize_into_reusing() {
+        let mut buf = Vec::new();
+
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("FOO=BAR")
+            .add_aux_v(AuxVar::Platform("x86_64"));
+        builder.serialize_into_reusing(&mut buf, None);
+        assert_eq!(buf.len(), builder.total_size());
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(
+            unsafe { parsed.argv_iter().collect::<Vec<_>>() },
+            ["first_arg\0"]
+        );
+        let capacity = buf.capacity();
+
+        let builder = InitialLinuxLibcStackLayoutBuilder::new().add_arg_v("a");
+        builder.serialize_into_reusing(&mut buf, None);
+        assert_eq!(buf.len(), builder.total_size());
+        assert_eq!(buf.capacity(), capacity);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(unsafe { parsed.argv_iter().collect::<Vec<_>>() }, ["a\0"]);
+        assert_eq!(parsed.envc(), 0);
+    }
+
+    #[test]
+    fn test_check_exec_exclusivity() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new().add_aux_v(AuxVar::ExecFd(3));
+        assert_eq!(builder.check_exec_exclusivity(), Ok(()));
+        let builder = builder.add_aux_v(AuxVar::ExecFn("/usr/bin/foo"));
+        assert_eq!(
+            builder.check_exec_exclusivity(),
+            Err(LayoutError::ConflictingExec)
+        );
+        let builder =
+            InitialLinuxLibcStackLayoutBuilder::new().add_aux_v(AuxVar::ExecFn("/usr/bin/foo"));
+        assert_eq!(builder.check_exec_exclusivity(), Ok(()));
+    }
+
+    #[test]
+    fn test_dedup_strings() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("./executable")
+            .add_arg_v("--foo")
+            .add_arg_v("--foo\0")
+            .add_env_v("FOO=BAR")
+            .add_env_v("PATH=/bin")
+            .add_env_v("FOO=BAR");
+        let dedup_builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("./executable")
+            .add_arg_v("--foo")
+            .add_arg_v("--foo\0")
+            .add_env_v("FOO=BAR")
+            .add_env_v("PATH=/bin")
+            .add_env_v("FOO=BAR")
+            .dedup_strings(true);
+        assert_eq!(
+            dedup_builder.argv_data_area_size(),
+            builder.argv_data_area_size() - 6
+        );
+        assert_eq!(
+            dedup_builder.envv_data_area_size(),
+            builder.envv_data_area_size() - 8
+        );
+        assert!(dedup_builder.total_size() < builder.total_size());
+
+        let buf = dedup_builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        unsafe {
+            assert_eq!(
+                parsed.argv_iter().collect::<Vec<_>>(),
+                ["./executable\0", "--foo\0", "--foo\0"]
+            );
+            assert_eq!(
+                parsed.envv_iter().collect::<Vec<_>>(),
+                ["FOO=BAR\0", "PATH=/bin\0", "FOO=BAR\0"]
+            );
+        }
+        assert_eq!(parsed.argv_ptrs()[1], parsed.argv_ptrs()[2]);
+        assert_eq!(parsed.envv_ptrs()[0], parsed.envv_ptrs()[2]);
+        assert_ne!(parsed.envv_ptrs()[0], parsed.envv_ptrs()[1]);
+    }
+
+    #[test]
+    fn test_vdso() {
+        let find = |builder: &InitialLinuxLibcStackLayoutBuilder, key| {
+            builder
+                .aux_v
+                .iter()
+                .find(|x| x.key() == key)
+                .and_then(AuxVar::value_ptr)
+        };
+        let builder = InitialLinuxLibcStackLayoutBuilder::new().vdso(0x7fff_f000);
+        assert_eq!(
+            find(&builder, AuxVarType::SysinfoEhdr),
+            Some(0x7fff_f000 as *const u8)
+        );
+        assert_eq!(find(&builder, AuxVarType::Sysinfo), None);
+
+        let builder =
+            InitialLinuxLibcStackLayoutBuilder::new().vdso_with_vsyscall(0x7fff_f000, 0x570);
+        assert_eq!(
+            find(&builder, AuxVarType::SysinfoEhdr),
+            Some(0x7fff_f000 as *const u8)
+        );
+        assert_eq!(
+            find(&builder, AuxVarType::Sysinfo),
+            Some(0x7fff_f570 as *const u8)
+        );
+
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(parsed.vdso_base(), Some(0x7fff_f000 as *const u8));
+    }
+
+    #[test]
+    fn test_abi_aligned() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .abi_aligned(true);
+        assert_eq!(builder.check_abi_alignment(0x7fff_0000), Ok(()));
+        let buf = builder.build_ref(Some(0x7fff_0000));
+        assert_eq!(InitialLinuxLibcStackLayout::from(buf.as_slice()).argc(), 1);
+
+        assert_eq!(
+            builder.check_abi_alignment(0x7fff_0008),
+            Err(BuildError::MisalignedStackPointer(0x7fff_0008))
+        );
+        assert_eq!(
+            builder.abi_aligned(false).check_abi_alignment(0x7fff_0008),
+            Ok(())
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_abi_aligned_panics() {
+        let _ = InitialLinuxLibcStackLayoutBuilder::new()
+            .abi_aligned(true)
+            .build_ref(Some(0x7fff_0008));
+    }
+
+    #[test]
+    fn test_try_build_ref() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .abi_aligned(true);
+        assert_eq!(
+            builder.try_build_ref(Some(0x7fff_0000)).unwrap(),
+            builder.build_ref(Some(0x7fff_0000))
+        );
+        assert_eq!(
+            builder.try_build_ref(Some(0x7fff_0008)),
+            Err(BuildError::MisalignedStackPointer(0x7fff_0008))
+        );
+        assert!(builder
+            .abi_aligned(false)
+            .try_build_ref(Some(0x7fff_0008))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_pad_to() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new().add_arg_v("first_arg");
+        let content_size = builder.total_size();
+        let builder = builder.pad_to(1024).unwrap();
+        assert_eq!(builder.total_size(), 1024);
+
+        let mut buf = vec![0xff; 1024];
+        unsafe {
+            builder.serialize_into_buf(&mut buf, 0x1000);
+        }
+        assert!(buf[content_size..].iter().all(|b| *b == 0));
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(parsed.argc(), 1);
+
+        assert_eq!(
+            InitialLinuxLibcStackLayoutBuilder::new()
+                .add_arg_v("first_arg")
+                .pad_to(16)
+                .unwrap_err(),
+            BuildError::TooLarge {
+                required: content_size,
+                limit: 16
+            }
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_envv_from_map() {
+        let map = ["PATH=/bin", "HOME=/root", "SHELL=/bin/sh", "LANG=C"]
+            .iter()
+            .map(|env| env.split_once('=').unwrap())
+            .map(|(key, value)| {
+                (
+                    std::string::String::from(key),
+                    std::string::String::from(value),
+                )
+            })
+            .collect::<std::collections::HashMap<_, _>>();
+        let build = || {
+            let mut storage = Vec::new();
+            InitialLinuxLibcStackLayoutBuilder::new()
+                .envv_from_map(&map, &mut storage)
+                .build_ref(Some(0x1000))
+        };
+        let mut storage = Vec::new();
+        let builder = InitialLinuxLibcStackLayoutBuilder::new().envv_from_map(&map, &mut storage);
+        assert_eq!(
+            builder.env_v,
+            ["HOME=/root", "LANG=C", "PATH=/bin", "SHELL=/bin/sh"]
+        );
+        assert_eq!(build(), build());
+    }
+
+    #[test]
+    fn test_try_add_env_v() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .try_add_env_v("FOO=BAR")
+            .unwrap()
+            .try_add_env_v("EMPTY=\0")
+            .unwrap();
+        assert_eq!(builder.env_v, ["FOO=BAR", "EMPTY=\0"]);
+
+        assert_eq!(
+            builder.try_add_env_v("FOO").unwrap_err(),
+            EnvSyntaxError::MissingEquals("FOO")
+        );
+        assert_eq!(
+            InitialLinuxLibcStackLayoutBuilder::new()
+                .try_add_env_v("=BAR")
+                .unwrap_err(),
+            EnvSyntaxError::EmptyKey("=BAR")
+        );
+        assert_eq!(
+            InitialLinuxLibcStackLayoutBuilder::new()
+                .try_add_env_v("FOO=B\0AR")
+                .unwrap_err(),
+            EnvSyntaxError::InteriorNul("FOO=B\0AR")
+        );
+    }
+
+    #[test]
+    fn test_execfn_placement() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("./prog")
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::ExecFn("./prog"));
+        let execfn_offset = |buf: &[u8]| {
+            let parsed = InitialLinuxLibcStackLayout::from(buf);
+            let execfn = unsafe { parsed.aux_var_iter() }
+                .find(|x| x.key() == AuxVarType::ExecFn)
+                .unwrap();
+            assert_eq!(execfn.value_payload_cstr(), Some("./prog"));
+            execfn.value_raw() - buf.as_ptr() as usize
+        };
+
+        let buf = builder.build_ref(None);
+        // right below the final null word
+        assert_eq!(execfn_offset(&buf), buf.len() - size_of::<u64>() - 7);
+
+        let builder = builder.execfn_placement(ExecFnPlacement::DataArea);
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let argv_data_offset = parsed.argv_ptrs()[0] - buf.as_ptr() as usize;
+        assert!(execfn_offset(&buf) >= builder.offset_to_aux_data_area());
+        assert!(execfn_offset(&buf) < argv_data_offset);
+        assert_eq!(builder.offset_to_final_null() + size_of::<u64>(), buf.len());
+    }
+
+    #[test]
+    fn test_minimal_valid() {
+        let buf =
+            InitialLinuxLibcStackLayoutBuilder::minimal_valid("./prog", [7; 16]).build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(parsed.argc(), 1);
+        assert_eq!(parsed.envc(), 0);
+        assert_eq!(
+            unsafe { parsed.argv_iter().collect::<Vec<_>>() },
+            ["./prog\0"]
+        );
+        let auxv = unsafe { parsed.aux_var_iter().collect::<Vec<_>>() };
+        let expected = [
+            AuxVar::Pagesz(4096),
+            AuxVar::Clktck(100),
+            AuxVar::Secure(false),
+            AuxVar::Random([7; 16]),
+            AuxVar::ExecFn("./prog"),
+            AuxVar::Null,
+        ];
+        assert_eq!(auxv.len(), expected.len());
+        assert!(auxv.iter().zip(&expected).all(|(a, b)| a.eq_content(b)));
+    }
+
+    #[test]
+    fn test_build_paged() {
+        const PAGESZ: usize = 4096;
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("ENV1=FOO")
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::ExecFn("./executable"));
+
+        // layout: entries page(s), guard page, data page(s)
+        let mut buf = vec![0_u8; 5 * PAGESZ];
+        let base = buf.as_ptr().align_offset(PAGESZ);
+        let entries_addr = buf[base..].as_ptr() as u64;
+        let data_addr = entries_addr + 2 * PAGESZ as u64;
+        let (entries, data) = builder.build_paged(entries_addr, data_addr, PAGESZ);
+        assert_eq!(entries.len(), PAGESZ);
+        assert_eq!(data.len(), PAGESZ);
+        buf[base..base + PAGESZ].copy_from_slice(&entries);
+        buf[base + 2 * PAGESZ..base + 3 * PAGESZ].copy_from_slice(&data);
+
+        let parsed = InitialLinuxLibcStackLayout::from(&buf[base..]);
+        unsafe {
+            assert_eq!(parsed.argv_iter().collect::<Vec<_>>(), ["first_arg\0"]);
+            assert_eq!(parsed.envv_iter().collect::<Vec<_>>(), ["ENV1=FOO\0"]);
+            let aux = parsed.aux_var_iter().collect::<Vec<_>>();
+            assert_eq!(aux[0].value_payload_cstr(), Some("x86_64"));
+            assert_eq!(aux[1].value_payload_cstr(), Some("./executable"));
+        }
+    }
+
+    #[test]
+    fn test_emit_trailing_sentinel() {
+        let new_builder = || {
+            InitialLinuxLibcStackLayoutBuilder::new()
+                .add_arg_v("first_arg")
+                .add_env_v("FOO=BAR")
+                .add_aux_v(AuxVar::Pagesz(4096))
+        };
+        let builder = new_builder();
+        let with_sentinel = new_builder().emit_trailing_sentinel(true);
+        assert_eq!(
+            with_sentinel.end_of_aux_key_area(),
+            builder.end_of_aux_key_area() + size_of::<usize>()
+        );
+        assert!(with_sentinel.total_size() >= builder.total_size());
+
+        // the destination isn't zeroed: the serializer must write the sentinel
+        let mut buf = vec![0xff_u8; with_sentinel.total_size()];
+        unsafe {
+            let user_ptr = buf.as_ptr() as u64;
+            with_sentinel.serialize_into_buf(&mut buf, user_ptr);
+        }
+        let sentinel_offset = builder.end_of_aux_key_area();
+        assert_eq!(
+            buf[sentinel_offset..sentinel_offset + size_of::<usize>()],
+            [0; size_of::<usize>()]
+        );
+
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(parsed.aux_serialized_iter().count(), 3);
+        unsafe {
+            assert_eq!(parsed.argv_iter().collect::<Vec<_>>(), ["first_arg\0"]);
+            assert_eq!(parsed.envv_iter().collect::<Vec<_>>(), ["FOO=BAR\0"]);
+            assert_eq!(
+                parsed
+                    .aux_var_iter()
+                    .find(|x| x.key() == AuxVarType::Pagesz),
+                Some(AuxVar::Pagesz(4096))
+            );
+        }
+    }
+
+    #[test]
+    fn test_layout_math_matches_builder() {
+        let args = ["a", "bb", "ccc", "dddd"];
+        let envs = ["A=1", "BB=22", "CCC=333"];
+        for argc in 0..args.len() {
+            for envc in 0..envs.len() {
+                let builder = args[..argc]
+                    .iter()
+                    .fold(InitialLinuxLibcStackLayoutBuilder::new(), |b, arg| {
+                        b.add_arg_v(arg)
+                    });
+                let builder = envs[..envc]
+                    .iter()
+                    .fold(builder, |b, env| b.add_env_v(env))
+                    .add_aux_v(AuxVar::Platform("x86_64"));
+                assert_eq!(
+                    builder.argv_keys_size(),
+                    layout_math::argv_entries_len(argc)
+                );
+                assert_eq!(
+                    builder.envv_keys_size(),
+                    layout_math::envv_entries_len(envc)
+                );
+                assert_eq!(
+                    builder.aux_keys_size(),
+                    layout_math::auxv_entries_len(3, layout_math::NATIVE_WORD_SIZE)
+                );
+                assert_eq!(
+                    builder.offset_to_aux_key_area(),
+                    layout_math::ARGC_SIZE
+                        + layout_math::argv_entries_len(argc)
+                        + layout_math::envv_entries_len(envc)
+                );
+                assert_eq!(
+                    builder.alignment_padding().before_aux_data,
+                    layout_math::align_up(builder.end_of_aux_key_area(), 16)
+                        - builder.end_of_aux_key_area()
+                );
+                assert_eq!(
+                    builder.offset_to_argv_data_area(),
+                    layout_math::align_up(builder.end_of_aux_data_area(), 16)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_add_aux_v_skip_null() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_aux_v_skip_null(AuxVar::Null)
+            .add_aux_v_skip_null(AuxVar::Pagesz(4096))
+            .add_aux_v_skip_null(AuxVar::Null);
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let keys = parsed
+            .aux_serialized_iter()
+            .map(|x| x.key())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [AuxVarType::Pagesz, AuxVarType::ExecFn, AuxVarType::Null]
+        );
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_size_delta() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new();
+        let other = InitialLinuxLibcStackLayoutBuilder::new().add_env_v("ABC=1234");
+        // 8 bytes for the envv entry, which fill the 8 bytes of padding before the aux data
+        // area, and 9 bytes for the C-string
+        assert_eq!(builder.size_delta(&other), 9);
+        assert_eq!(other.size_delta(&builder), -9);
+        assert_eq!(builder.size_delta(&builder), 0);
+    }
 }
@@ -22,7 +22,7 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 //! Module for [`AuxvSerializer`].
-use crate::builder::InitialLinuxLibcStackLayoutBuilder;
+use crate::builder::{ExecFnPlacement, InitialLinuxLibcStackLayoutBuilder};
 use crate::cstr_util::c_str_null_terminated;
 use crate::{AuxVar, AuxVarType};
 use core::mem::size_of;
@@ -55,12 +55,13 @@ pub(super) struct AuxvSerializer<'a> {
     filename_write_ptr: *mut u8,
     /// The final null pointer.
     final_null_ptr: *mut u8,
-    /// Base pointer in user address space. Args, Env Vars, and AT vars with referenced data
-    /// will point to a specific user address rather than a relative offset (why solve things
-    /// easy when you can make it complicated?! thanks Linux). Therefore this is used to calc
-    /// the address in user address space so that the libc can resolve all references on valid
-    /// memory.
-    user_addr: u64,
+    /// Address of the aux data area (the first data area) in user address space. Args, Env Vars,
+    /// and AT vars with referenced data will point to a specific user address rather than a
+    /// relative offset (why solve things easy when you can make it complicated?! thanks Linux).
+    /// Therefore this is used to calc the address in user address space so that the libc can
+    /// resolve all references on valid memory. All pointers point into the data areas, hence,
+    /// the data areas may live somewhere else than the entries in user address space.
+    data_user_addr: u64,
 
     /// Number of args written. Used for runtime checks.
     arg_write_count: usize,
@@ -71,10 +72,26 @@ pub(super) struct AuxvSerializer<'a> {
 }
 
 impl<'a> AuxvSerializer<'a> {
+    /// Creates a serializer for a data structure that lives at `user_addr` in user address
+    /// space as one contiguous block.
     pub fn new(
         builder: &'a InitialLinuxLibcStackLayoutBuilder,
         begin_ptr: *mut u8,
         user_addr: u64,
+    ) -> Self {
+        let data_user_addr = user_addr
+            .checked_add(builder.offset_to_aux_data_area() as u64)
+            .expect("the user address overflows!");
+        Self::new_split(builder, begin_ptr, data_user_addr)
+    }
+
+    /// Creates a serializer for a data structure, where the data areas live at `data_user_addr`
+    /// in user address space, independent of the location of the entries (argc, argv, envv,
+    /// auxv).
+    pub fn new_split(
+        builder: &'a InitialLinuxLibcStackLayoutBuilder,
+        begin_ptr: *mut u8,
+        data_user_addr: u64,
     ) -> Self {
         unsafe {
             Self {
@@ -89,7 +106,7 @@ impl<'a> AuxvSerializer<'a> {
                 aux_data_write_ptr: begin_ptr.add(builder.offset_to_aux_data_area()),
                 filename_write_ptr: begin_ptr.add(builder.offset_to_filename_data_area()),
                 final_null_ptr: begin_ptr.add(builder.offset_to_final_null()),
-                user_addr,
+                data_user_addr,
                 arg_write_count: 0,
                 env_write_count: 0,
                 aux_write_count: 0,
@@ -108,6 +125,19 @@ impl<'a> AuxvSerializer<'a> {
             "More arguments have been written than capacity is available!"
         );
 
+        if let Some(index) = self
+            .builder
+            .shared_string_index(&self.builder.arg_v, self.arg_write_count)
+        {
+            // point to the already written copy of the identical string
+            let key_ptr = self.argv_key_write_ptr.cast::<u64>();
+            let shared_ptr = core::ptr::read(key_ptr.sub(self.arg_write_count - index));
+            core::ptr::write(key_ptr, shared_ptr);
+            self.argv_key_write_ptr = self.argv_key_write_ptr.add(size_of::<u64>());
+            self.arg_write_count += 1;
+            return;
+        }
+
         core::ptr::write(
             self.argv_key_write_ptr.cast(),
             self.to_user_ptr(self.argv_data_write_ptr),
@@ -138,6 +168,19 @@ impl<'a> AuxvSerializer<'a> {
             "More arguments have been written than capacity is available!"
         );
 
+        if let Some(index) = self
+            .builder
+            .shared_string_index(&self.builder.env_v, self.env_write_count)
+        {
+            // point to the already written copy of the identical string
+            let key_ptr = self.envv_key_write_ptr.cast::<u64>();
+            let shared_ptr = core::ptr::read(key_ptr.sub(self.env_write_count - index));
+            core::ptr::write(key_ptr, shared_ptr);
+            self.envv_key_write_ptr = self.envv_key_write_ptr.add(size_of::<u64>());
+            self.env_write_count += 1;
+            return;
+        }
+
         core::ptr::write(
             self.envv_key_write_ptr.cast(),
             self.to_user_ptr(self.envv_data_write_ptr),
@@ -173,11 +216,10 @@ impl<'a> AuxvSerializer<'a> {
         // increment 1/2
         self.aux_key_write_ptr = self.aux_key_write_ptr.add(size_of::<usize>());
 
-        // TODO maybe move away from key
-        if !aux_var.key().value_in_data_area() {
+        if let Ok(val) = aux_var.try_value_raw() {
             // write integer, "external" pointer, or boolean, but no pointer referencing data in
             // aux data area
-            core::ptr::write(self.aux_key_write_ptr.cast::<usize>(), aux_var.value_raw());
+            core::ptr::write(self.aux_key_write_ptr.cast::<usize>(), val);
         } else {
             // Pointer to the pointer of the C-string, either into aux vec data area or
             // into filename data area
@@ -186,10 +228,12 @@ impl<'a> AuxvSerializer<'a> {
             let is_c_str;
 
             // special treatment for AT_EXEC_FN; see https://lwn.net/Articles/631631/
-            if aux_var.key() == AuxVarType::ExecFn {
+            if aux_var.key() == AuxVarType::ExecFn
+                && self.builder.execfn_placement == ExecFnPlacement::TopOfStack
+            {
                 data_write_ptr_ptr = &mut self.filename_write_ptr as *mut _;
                 bytes = aux_var.value_payload_cstr().unwrap().as_bytes();
-                is_c_str = false;
+                is_c_str = true;
             } else {
                 data_write_ptr_ptr = &mut self.aux_data_write_ptr as *mut _;
                 if let Some(cstr) = aux_var.value_payload_cstr() {
@@ -224,9 +268,66 @@ impl<'a> AuxvSerializer<'a> {
         self.aux_write_count += 1;
     }
 
-    /// Writes a final NULL-ptr into the data structure.
+    /// Writes the zero word that follows the terminating [`AuxVarType::Null`] entry. See
+    /// [`InitialLinuxLibcStackLayoutBuilder::emit_trailing_sentinel`].
+    pub unsafe fn write_trailing_sentinel(&mut self) {
+        core::ptr::write(self.aux_key_write_ptr.cast::<usize>(), 0);
+        self.aux_key_write_ptr = self.aux_key_write_ptr.add(size_of::<usize>());
+    }
+
+    /// Writes a final NULL-ptr into the data structure. The location directly follows the
+    /// file name and is therefore usually not aligned. The whole reserved `u64` is written, so
+    /// that the top of the structure is zeroed independently of the pointer width.
     pub unsafe fn write_finish(&mut self) {
-        core::ptr::write(self.final_null_ptr.cast::<*const u8>(), core::ptr::null());
+        core::ptr::write_unaligned(self.final_null_ptr.cast::<u64>(), 0);
+        self.debug_assert_fully_written();
+    }
+
+    /// Checks that the write pointers ended exactly at the offsets, that the builder calculated
+    /// beforehand. A mismatch means that the size calculation of the builder and the writes of
+    /// the serializer drifted apart, which corrupts the data structure.
+    fn debug_assert_fully_written(&self) {
+        let builder = self.builder;
+        let offset = |ptr: *mut u8| self.get_write_ptr_offset(ptr);
+        debug_assert_eq!(self.arg_write_count, builder.arg_v.len());
+        debug_assert_eq!(self.env_write_count, builder.env_v.len());
+        debug_assert_eq!(self.aux_write_count, builder.aux_v.len());
+        // the key write pointers point to the terminating null pointer of their array
+        debug_assert_eq!(
+            offset(self.argv_key_write_ptr) + size_of::<u64>(),
+            builder.offset_to_envv_key_area(),
+            "argv keys don't match the calculated size"
+        );
+        debug_assert_eq!(
+            offset(self.envv_key_write_ptr) + size_of::<u64>(),
+            builder.offset_to_aux_key_area(),
+            "envv keys don't match the calculated size"
+        );
+        debug_assert_eq!(
+            offset(self.aux_key_write_ptr),
+            builder.end_of_aux_key_area(),
+            "aux keys don't match the calculated size"
+        );
+        debug_assert_eq!(
+            offset(self.aux_data_write_ptr),
+            builder.end_of_aux_data_area(),
+            "aux data doesn't match the calculated size"
+        );
+        debug_assert_eq!(
+            offset(self.argv_data_write_ptr),
+            builder.offset_to_env_data_area(),
+            "argv data doesn't match the calculated size"
+        );
+        debug_assert_eq!(
+            offset(self.envv_data_write_ptr),
+            builder.offset_to_filename_data_area(),
+            "envv data doesn't match the calculated size"
+        );
+        debug_assert_eq!(
+            offset(self.filename_write_ptr),
+            builder.offset_to_final_null(),
+            "file name doesn't match the calculated size"
+        );
     }
 
     /// Helper function for all serializations of C-strings. For convenience reasons they don't
@@ -255,7 +356,11 @@ impl<'a> AuxvSerializer<'a> {
 
     /// Transforms the write pointer into the corresponding pointer in the user address space.
     fn to_user_ptr(&self, write_ptr: *const u8) -> u64 {
-        self.user_addr + self.get_write_ptr_offset(write_ptr) as u64
+        let data_area_offset =
+            self.get_write_ptr_offset(write_ptr) - self.builder.offset_to_aux_data_area();
+        self.data_user_addr
+            .checked_add(data_area_offset as u64)
+            .expect("the user address overflows!")
     }
 }
 
@@ -519,4 +624,31 @@ mod tests {
             assert!(writer.argv_key_write_ptr > writer.argc_write_ptr);
         }
     }
+
+    #[test]
+    #[should_panic(expected = "the user address overflows!")]
+    fn test_to_user_ptr_overflow() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("prog")
+            .add_aux_v(AuxVar::Platform("x86_64"));
+        let mut buf = vec![0_u8; builder.total_size()];
+        // the args data area is behind the aux data area
+        let mut writer = AuxvSerializer::new_split(&builder, buf.as_mut_ptr(), u64::MAX - 2);
+        unsafe {
+            writer.write_arg("prog");
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "argv data doesn't match the calculated size")]
+    fn test_debug_assert_fully_written() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new().add_arg_v("prog");
+        // the serializer calculates the sizes for a shorter argument than is written
+        let sized_for = InitialLinuxLibcStackLayoutBuilder::new().add_arg_v("pro");
+        let mut buf = vec![0_u8; builder.total_size()];
+        unsafe {
+            builder.serialize(AuxvSerializer::new(&sized_for, buf.as_mut_ptr(), 0x1000));
+        }
+    }
 }
@@ -0,0 +1,202 @@
+/*
+MIT License
+
+Copyright (c) 2021 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`StrictStackLayoutBuilder`].
+
+use crate::{AuxVar, InitialLinuxLibcStackLayoutBuilder};
+use core::marker::PhantomData;
+
+/// Marker type for [`StrictStackLayoutBuilder`]: the mandatory aux entry is not set yet.
+#[derive(Debug)]
+pub struct AuxVarMissing;
+
+/// Marker type for [`StrictStackLayoutBuilder`]: the mandatory aux entry is set.
+#[derive(Debug)]
+pub struct AuxVarSet;
+
+/// Wrapper around [`InitialLinuxLibcStackLayoutBuilder`] that checks mandatory aux entries
+/// at compile time.
+///
+/// The entries [`crate::AuxVarType::Entry`], [`crate::AuxVarType::Phdr`],
+/// [`crate::AuxVarType::Phent`], and [`crate::AuxVarType::Phnum`] are required by a dynamic
+/// loader to start the program. [`Self::build`] is only available, once all four of them
+/// were set.
+///
+/// ```compile_fail
+/// use linux_libc_auxv::StrictStackLayoutBuilder;
+/// // AT_PHNUM is missing
+/// let builder = StrictStackLayoutBuilder::new()
+///     .entry(0x401000 as *const u8)
+///     .phdr(0x400040 as *const u8)
+///     .phent(56)
+///     .build();
+/// ```
+///
+/// ```
+/// use linux_libc_auxv::StrictStackLayoutBuilder;
+/// let builder = StrictStackLayoutBuilder::new()
+///     .add_arg_v("./first_arg")
+///     .entry(0x401000 as *const u8)
+///     .phdr(0x400040 as *const u8)
+///     .phent(56)
+///     .phnum(4)
+///     .build();
+/// let mut buf = vec![0; builder.total_size()];
+/// unsafe {
+///     builder.serialize_into_buf(&mut buf, 0x7fff0000);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct StrictStackLayoutBuilder<'a, Entry, Phdr, Phent, Phnum> {
+    builder: InitialLinuxLibcStackLayoutBuilder<'a>,
+    _marker: PhantomData<(Entry, Phdr, Phent, Phnum)>,
+}
+
+impl<'a> StrictStackLayoutBuilder<'a, AuxVarMissing, AuxVarMissing, AuxVarMissing, AuxVarMissing> {
+    /// Creates a new builder without any of the mandatory aux entries.
+    pub fn new() -> Self {
+        Self {
+            builder: InitialLinuxLibcStackLayoutBuilder::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a> Default
+    for StrictStackLayoutBuilder<'a, AuxVarMissing, AuxVarMissing, AuxVarMissing, AuxVarMissing>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, Entry, Phdr, Phent, Phnum> StrictStackLayoutBuilder<'a, Entry, Phdr, Phent, Phnum> {
+    /// See [`InitialLinuxLibcStackLayoutBuilder::add_arg_v`].
+    pub fn add_arg_v(mut self, c_str: &'a str) -> Self {
+        self.builder = self.builder.add_arg_v(c_str);
+        self
+    }
+
+    /// See [`InitialLinuxLibcStackLayoutBuilder::add_env_v`].
+    pub fn add_env_v(mut self, c_str: &'a str) -> Self {
+        self.builder = self.builder.add_env_v(c_str);
+        self
+    }
+
+    /// See [`InitialLinuxLibcStackLayoutBuilder::add_aux_v`]. Use the dedicated methods for the
+    /// mandatory entries, otherwise they are not recognized as set.
+    pub fn add_aux_v(mut self, var: AuxVar<'a>) -> Self {
+        self.builder = self.builder.add_aux_v(var);
+        self
+    }
+
+    /// Sets [`AuxVar::Entry`].
+    pub fn entry(
+        self,
+        entry: *const u8,
+    ) -> StrictStackLayoutBuilder<'a, AuxVarSet, Phdr, Phent, Phnum> {
+        StrictStackLayoutBuilder {
+            builder: self.builder.add_aux_v(AuxVar::Entry(entry)),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets [`AuxVar::Phdr`].
+    pub fn phdr(
+        self,
+        phdr: *const u8,
+    ) -> StrictStackLayoutBuilder<'a, Entry, AuxVarSet, Phent, Phnum> {
+        StrictStackLayoutBuilder {
+            builder: self.builder.add_aux_v(AuxVar::Phdr(phdr)),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets [`AuxVar::Phent`].
+    pub fn phent(
+        self,
+        phent: usize,
+    ) -> StrictStackLayoutBuilder<'a, Entry, Phdr, AuxVarSet, Phnum> {
+        StrictStackLayoutBuilder {
+            builder: self.builder.add_aux_v(AuxVar::Phent(phent)),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets [`AuxVar::Phnum`].
+    pub fn phnum(
+        self,
+        phnum: usize,
+    ) -> StrictStackLayoutBuilder<'a, Entry, Phdr, Phent, AuxVarSet> {
+        StrictStackLayoutBuilder {
+            builder: self.builder.add_aux_v(AuxVar::Phnum(phnum)),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a> StrictStackLayoutBuilder<'a, AuxVarSet, AuxVarSet, AuxVarSet, AuxVarSet> {
+    /// Returns the underlying [`InitialLinuxLibcStackLayoutBuilder`], which is guaranteed to
+    /// contain all mandatory aux entries. Use it to serialize the data structure.
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn build(self) -> InitialLinuxLibcStackLayoutBuilder<'a> {
+        self.builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AuxVarType, InitialLinuxLibcStackLayout};
+    use std::vec::Vec;
+
+    #[test]
+    fn test_strict_builder() {
+        let builder = StrictStackLayoutBuilder::new()
+            .phnum(4)
+            .add_arg_v("first_arg")
+            .phent(56)
+            .entry(0x401000 as *const u8)
+            .add_aux_v(AuxVar::Pagesz(4096))
+            .phdr(0x400040 as *const u8)
+            .build();
+        let mut buf = vec![0; builder.total_size()];
+        unsafe {
+            builder.serialize_into_buf(&mut buf, 0x1000);
+        }
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let keys = parsed
+            .aux_serialized_iter()
+            .map(|aux| aux.key())
+            .collect::<Vec<_>>();
+        for key in [
+            AuxVarType::Entry,
+            AuxVarType::Phdr,
+            AuxVarType::Phent,
+            AuxVarType::Phnum,
+            AuxVarType::Pagesz,
+        ] {
+            assert!(keys.contains(&key));
+        }
+    }
+}
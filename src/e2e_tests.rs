@@ -0,0 +1,169 @@
+/*
+MIT License
+
+Copyright (c) 2021 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! End-to-end tests: The builder reproduces the data structure, that Linux created for a real
+//! program. The layout is parsed again with [`InitialLinuxLibcStackLayout`] and must contain
+//! what the program received from Linux, i.e., what its libc reads in `_start`.
+//!
+//! References are the captured layout of [`crate::test_data`] and, as ignored test, a child
+//! process that is spawned during the test run. Entries of the auxiliary vector that reference
+//! data in the address space of the original program are not compared.
+
+use crate::{AuxVarType, InitialLinuxLibcStackLayout, InitialLinuxLibcStackLayoutBuilder};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// What a program received from Linux.
+struct Reference {
+    argv: Vec<String>,
+    envv: Vec<String>,
+    /// Raw `(key, value)`-pairs of the auxiliary vector without the terminating entry.
+    auxv: Vec<(usize, usize)>,
+}
+
+impl Reference {
+    /// Returns the aux entries that the builder can reproduce, i.e., known types whose value
+    /// doesn't live in the aux data area of the original program. [`AuxVarType::Ignore`] is
+    /// skipped, because it may occur multiple times, but the builder holds one entry per type.
+    fn reproducible_auxv(&self) -> Vec<(usize, usize)> {
+        self.auxv
+            .iter()
+            .copied()
+            .filter(|(key, _)| {
+                matches!(
+                    AuxVarType::try_from(*key),
+                    Ok(typ) if !typ.value_in_data_area() && typ != AuxVarType::Ignore
+                )
+            })
+            .collect()
+    }
+}
+
+/// Builds a layout that mirrors `reference` and checks that a libc reads the same values as
+/// the original program.
+fn assert_reproduces(reference: &Reference) {
+    let auxv = reference.reproducible_auxv();
+    let builder = reference
+        .argv
+        .iter()
+        .fold(InitialLinuxLibcStackLayoutBuilder::new(), |b, arg| {
+            b.add_arg_v(arg)
+        });
+    let builder = reference
+        .envv
+        .iter()
+        .fold(builder, |b, env| b.add_env_v(env))
+        .aux_v_from_raw_pairs(&auxv, |_| None)
+        .unwrap();
+    let buf = builder.build_ref(None);
+
+    let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+    assert_eq!(parsed.check_argc_consistency(), Ok(()));
+    assert_eq!(parsed.check_auxv_terminator(), Ok(()));
+    assert_eq!(parsed.check_monotonic_data_pointers(), Ok(()));
+
+    let trim_null = |cstr: &str| String::from(cstr.strip_suffix('\0').unwrap_or(cstr));
+    let argv = unsafe { parsed.argv_iter() }
+        .map(trim_null)
+        .collect::<Vec<_>>();
+    let envv = unsafe { parsed.envv_iter() }
+        .map(trim_null)
+        .collect::<Vec<_>>();
+    assert_eq!(argv, reference.argv);
+    assert_eq!(envv, reference.envv);
+
+    let mut actual_auxv = parsed
+        .aux_serialized_iter()
+        .filter(|aux| !aux.key().value_in_data_area())
+        .filter(|aux| !matches!(aux.key(), AuxVarType::Null | AuxVarType::Ignore))
+        .map(|aux| (aux.key().val(), aux.val()))
+        .collect::<Vec<_>>();
+    let mut expected_auxv = auxv;
+    actual_auxv.sort_unstable();
+    expected_auxv.sort_unstable();
+    assert_eq!(actual_auxv, expected_auxv);
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn test_e2e_captured_reference() {
+    let relocated = crate::test_data::test_data_x86_64_relocated();
+    let captured = InitialLinuxLibcStackLayout::from(relocated.as_slice());
+    let to_strings = |iter: crate::CstrIter| {
+        iter.map(|cstr| String::from(cstr.trim_end_matches('\0')))
+            .collect::<Vec<_>>()
+    };
+    let reference = Reference {
+        argv: to_strings(unsafe { captured.argv_iter() }),
+        envv: to_strings(unsafe { captured.envv_iter() }),
+        auxv: captured
+            .aux_serialized_iter()
+            .filter(|aux| aux.key() != AuxVarType::Null)
+            .map(|aux| (aux.key().val(), aux.val()))
+            .collect(),
+    };
+    // see the command line in the documentation of the test data
+    assert_eq!(reference.argv, ["./cap", "first", "second", "third"]);
+    assert_eq!(
+        reference.envv,
+        ["HOME=/root", "TERM=xterm-256color", "PATH=/bin:/usr/bin"]
+    );
+    assert_reproduces(&reference);
+}
+
+/// Spawns a real program with a known command line and environment, which prints the
+/// auxiliary vector that it received from Linux. Ignored by default, because it depends on
+/// the host. Run it with `cargo test --features std -- --ignored`.
+#[test]
+#[ignore]
+#[cfg(all(feature = "std", target_os = "linux"))]
+fn test_e2e_spawned_process() {
+    use core::mem::size_of;
+    use std::process::Command;
+
+    const WORD_SIZE: usize = size_of::<usize>();
+    let output = Command::new("cat")
+        .arg("/proc/self/auxv")
+        .env_clear()
+        .env("FOO", "BAR")
+        .output()
+        .expect("cat must be available");
+    assert!(output.status.success());
+    let auxv = output
+        .stdout
+        .chunks_exact(2 * WORD_SIZE)
+        .map(|pair| {
+            let word = |bytes: &[u8]| usize::from_ne_bytes(bytes.try_into().unwrap());
+            (word(&pair[..WORD_SIZE]), word(&pair[WORD_SIZE..]))
+        })
+        .take_while(|(key, _)| *key != AuxVarType::Null.val())
+        .collect::<Vec<_>>();
+    assert!(auxv.iter().any(|(key, _)| *key == AuxVarType::Pagesz.val()));
+
+    let reference = Reference {
+        argv: vec![String::from("cat"), String::from("/proc/self/auxv")],
+        envv: vec![String::from("FOO=BAR")],
+        auxv,
+    };
+    assert_reproduces(&reference);
+}
@@ -0,0 +1,251 @@
+/*
+MIT License
+
+Copyright (c) 2021 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`LayoutError`], [`BuildError`], [`TypeMismatchError`], and other error types.
+use core::fmt::{Display, Formatter};
+
+/// Errors that are reported by the validation helpers of [`crate::InitialLinuxLibcStackLayout`],
+/// if the parsed data structure doesn't look like a well-formed initial stack layout.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    /// A pointer into one of the data areas is smaller than a pointer that was found before it,
+    /// although the data areas are written in ascending order.
+    NonMonotonicDataPointer {
+        /// The previous (bigger) pointer.
+        previous: usize,
+        /// The pointer that violates the order.
+        current: usize,
+    },
+    /// The value of `argc` doesn't match the number of entries in the null-terminated
+    /// `argv`-array.
+    ArgcMismatch {
+        /// The value of `argc` that is stored in the data structure.
+        argc: usize,
+        /// The number of non-null pointers in the `argv`-array within the buffer.
+        actual: usize,
+    },
+    /// The terminating [`crate::AuxVarType::Null`] entry of the auxiliary vector has a
+    /// value other than zero.
+    NonZeroAtNull(usize),
+    /// The auxiliary vector has no terminating [`crate::AuxVarType::Null`] entry within the
+    /// buffer.
+    MissingAuxvTerminator,
+    /// The key of an auxiliary vector entry is not a known [`crate::AuxVarType`].
+    UnknownAuxVarType(usize),
+    /// The data that is referenced by an auxiliary vector entry can't be resolved or doesn't
+    /// match the type, e.g., a C-string that is not valid UTF-8.
+    InvalidAuxData(crate::AuxVarType),
+    /// The value of an auxiliary vector entry points to data outside of the buffer, which can't
+    /// be resolved safely.
+    PointerOutOfRange(crate::AuxVarType),
+    /// The length header of a framed data structure doesn't match the length of the payload.
+    FrameLengthMismatch {
+        /// The length in the header.
+        header: u64,
+        /// The actual length of the payload.
+        actual: usize,
+    },
+    /// The data structure doesn't begin at a word-aligned address.
+    UnalignedBuffer,
+    /// Both [`crate::AuxVarType::ExecFd`] and a non-default [`crate::AuxVarType::ExecFn`]
+    /// are set, although a program is either loaded from a path or from a file descriptor.
+    ConflictingExec,
+    /// The buffer is too small to contain the data that is accessed.
+    Truncated,
+    /// The data structure has more entries than the provided storage can hold.
+    CapacityExceeded {
+        /// The number of entries in the data structure.
+        required: usize,
+        /// The number of entries the storage can hold.
+        capacity: usize,
+    },
+    /// The value of `argc` doesn't match the value that the caller expects, e.g., in
+    /// [`crate::InitialLinuxLibcStackLayout::try_new`].
+    ArgcUnexpected {
+        /// The value of `argc` that is stored in the data structure.
+        stored: usize,
+        /// The value that the caller expects.
+        expected: usize,
+    },
+    /// A pointer of the `argv`- or `envv`-array points outside of the buffer.
+    DataPointerOutOfRange(usize),
+}
+
+impl Display for LayoutError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NonMonotonicDataPointer { previous, current } => write!(
+                f,
+                "data pointer {:#x} is smaller than the previous data pointer {:#x}",
+                current, previous
+            ),
+            Self::ArgcMismatch { argc, actual } => write!(
+                f,
+                "argc is {} but the argv array has {} entries",
+                argc, actual
+            ),
+            Self::NonZeroAtNull(val) => write!(f, "value of AT_NULL is {:#x} instead of 0", val),
+            Self::MissingAuxvTerminator => write!(f, "the auxiliary vector has no AT_NULL entry"),
+            Self::UnknownAuxVarType(key) => write!(f, "unknown auxiliary vector type {}", key),
+            Self::InvalidAuxData(key) => write!(f, "invalid referenced data for {:?}", key),
+            Self::PointerOutOfRange(key) => {
+                write!(f, "the data of {:?} is not inside the buffer", key)
+            }
+            Self::FrameLengthMismatch { header, actual } => write!(
+                f,
+                "the frame header announces {} bytes but the payload has {} bytes",
+                header, actual
+            ),
+            Self::UnalignedBuffer => write!(f, "the data structure is not word-aligned"),
+            Self::ConflictingExec => write!(f, "both AT_EXECFD and AT_EXECFN are set"),
+            Self::Truncated => write!(f, "the buffer is truncated"),
+            Self::CapacityExceeded { required, capacity } => write!(
+                f,
+                "{} entries don't fit into a storage for {} entries",
+                required, capacity
+            ),
+            Self::ArgcUnexpected { stored, expected } => {
+                write!(f, "argc is {} but {} was expected", stored, expected)
+            }
+            Self::DataPointerOutOfRange(ptr) => {
+                write!(f, "the pointer {:#x} is not inside the buffer", ptr)
+            }
+        }
+    }
+}
+
+/// Errors that are reported by the validation helpers of
+/// [`crate::InitialLinuxLibcStackLayoutBuilder`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// An address in the data structure doesn't fit into a word of the target.
+    AddressOverflow {
+        /// The biggest address of the data structure in the user address space.
+        addr: u64,
+        /// The word size of the target.
+        word_size: crate::WordSize,
+    },
+    /// The address of `argc` is not 16-byte aligned, although the ABI requires it. See
+    /// [`crate::InitialLinuxLibcStackLayoutBuilder::abi_aligned`].
+    MisalignedStackPointer(u64),
+    /// The data structure is bigger than the requested size. See
+    /// [`crate::InitialLinuxLibcStackLayoutBuilder::pad_to`].
+    TooLarge {
+        /// The size of the content of the data structure.
+        required: usize,
+        /// The requested size.
+        limit: usize,
+    },
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::AddressOverflow { addr, word_size } => write!(
+                f,
+                "address {:#x} doesn't fit into a word of size {} bytes",
+                addr,
+                word_size.bytes()
+            ),
+            Self::MisalignedStackPointer(addr) => {
+                write!(f, "stack pointer {:#x} is not 16-byte aligned", addr)
+            }
+            Self::TooLarge { required, limit } => write!(
+                f,
+                "the data structure needs {} bytes but only {} bytes are requested",
+                required, limit
+            ),
+        }
+    }
+}
+
+/// Error of [`crate::AuxVar::from_referenced`], if the referenced bytes don't fit the
+/// [`crate::AuxVarType`].
+///
+/// This is the case for types, whose value doesn't live in the aux
+/// data area, for C-strings that are not valid UTF-8, and for a wrong length of fixed-size
+/// data.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TypeMismatchError(pub crate::AuxVarType);
+
+impl Display for TypeMismatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "the referenced data doesn't match the type {:?}", self.0)
+    }
+}
+
+/// Error of [`crate::AuxVar::try_value_raw`], if the variant references data in the aux data
+/// area and therefore has no standalone integer value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DataAreaValueError(pub crate::AuxVarType);
+
+impl Display for DataAreaValueError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{:?} references data in the aux data area and has no standalone integer value",
+            self.0
+        )
+    }
+}
+
+/// Error of [`crate::AuxVarType::try_from`], if the value is not a known type, e.g., a type
+/// of a newer kernel. Contains the value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseAuxVarTypeError(pub usize);
+
+impl Display for ParseAuxVarTypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "unknown auxiliary vector type {}", self.0)
+    }
+}
+
+impl From<ParseAuxVarTypeError> for LayoutError {
+    fn from(err: ParseAuxVarTypeError) -> Self {
+        Self::UnknownAuxVarType(err.0)
+    }
+}
+
+/// Error of [`crate::InitialLinuxLibcStackLayoutBuilder::try_add_env_v`], if an environment
+/// variable doesn't have the format `KEY=VALUE`. Contains the offending string.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EnvSyntaxError<'a> {
+    /// The string has no `=`.
+    MissingEquals(&'a str),
+    /// The string begins with `=`, i.e., the key is empty.
+    EmptyKey(&'a str),
+    /// The string contains a null byte that is not the terminating one.
+    InteriorNul(&'a str),
+}
+
+impl Display for EnvSyntaxError<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::MissingEquals(env) => write!(f, "environment variable {:?} has no '='", env),
+            Self::EmptyKey(env) => write!(f, "environment variable {:?} has an empty key", env),
+            Self::InteriorNul(env) => {
+                write!(f, "environment variable {:?} contains a null byte", env)
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+/*
+MIT License
+
+Copyright (c) 2021 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Pure functions to calculate the offsets and sizes of the data structure.
+//!
+//! They don't need an [`crate::InitialLinuxLibcStackLayoutBuilder`]. These are the primitives
+//! that the builder uses internally. This is useful to lay out a stack by hand, e.g., in a VM.
+//!
+//! The module is public with the `layout-math` feature.
+use crate::{AuxVarType, WordSize};
+use core::mem::size_of;
+
+/// Alignment of the data areas, if no bigger alignment is requested.
+pub const DATA_AREA_ALIGNMENT: usize = 16;
+
+/// Size in bytes of `argc` at the beginning of the data structure.
+pub const ARGC_SIZE: usize = size_of::<u64>();
+
+/// Word size of the target, this crate is compiled for.
+#[cfg(target_pointer_width = "32")]
+pub const NATIVE_WORD_SIZE: WordSize = WordSize::Bits32;
+/// Word size of the target, this crate is compiled for.
+#[cfg(target_pointer_width = "64")]
+pub const NATIVE_WORD_SIZE: WordSize = WordSize::Bits64;
+
+/// Returns the size in bytes of the `argv`-array with `argc` entries.
+///
+/// This includes the terminating null pointer. Only the entries, but not the referenced data.
+pub const fn argv_entries_len(argc: usize) -> usize {
+    // +1: null terminated
+    size_of::<u64>() * (argc + 1)
+}
+
+/// Returns the size in bytes of the `envv`-array with `envc` entries.
+///
+/// This includes the terminating null pointer. Only the entries, but not the referenced data.
+pub const fn envv_entries_len(envc: usize) -> usize {
+    // +1: null terminated
+    size_of::<u64>() * (envc + 1)
+}
+
+/// Returns the size in bytes of the auxiliary vector with `auxc` entries.
+///
+/// The size of an entry depends on the word size of the target. `auxc` must include the
+/// terminating [`AuxVarType::Null`] entry. Only the entries, but not the referenced data.
+pub const fn auxv_entries_len(auxc: usize, word_size: WordSize) -> usize {
+    auxc * AuxVarType::serialized_pair_size(word_size)
+}
+
+/// Rounds `offset` up to the next multiple of `alignment`, which must be a power of two.
+pub const fn align_up(offset: usize, alignment: usize) -> usize {
+    (offset + alignment - 1) & !(alignment - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_align_up() {
+        assert_eq!(align_up(0, 16), 0);
+        assert_eq!(align_up(1, 16), 16);
+        assert_eq!(align_up(16, 16), 16);
+        assert_eq!(align_up(17, 4096), 4096);
+    }
+
+    #[test]
+    fn test_entries_len() {
+        assert_eq!(argv_entries_len(0), 8);
+        assert_eq!(envv_entries_len(3), 32);
+        assert_eq!(auxv_entries_len(3, WordSize::Bits32), 24);
+        assert_eq!(auxv_entries_len(3, WordSize::Bits64), 48);
+    }
+}
@@ -173,6 +173,8 @@ SOFTWARE.
 //! ## MSRV
 //! 1.56.1 stable / Rust edition 2021
 //!
+//! The optional `serde` feature needs a newer Rust version, as required by `serde_derive`.
+//!
 //! ## Background Information & Links
 //! - <https://lwn.net/Articles/631631/> (good overview with ASCII graphics)
 //! - <https://lwn.net/Articles/519085/>
@@ -195,19 +197,34 @@ SOFTWARE.
 #![deny(missing_debug_implementations)]
 #![deny(rustdoc::all)]
 #![no_std]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 
 mod aux_var;
 mod builder;
 mod cstr_util;
+#[cfg(test)]
+mod e2e_tests;
+mod error;
+#[cfg(feature = "layout-math")]
+pub mod layout_math;
+#[cfg(not(feature = "layout-math"))]
+mod layout_math;
 mod parser;
+#[cfg(test)]
+mod roundtrip_tests;
+mod snapshot;
+#[cfg(all(test, target_arch = "x86_64"))]
+mod test_data;
 
 pub use aux_var::*;
 pub use builder::*;
+pub use error::*;
 pub use parser::*;
+pub use snapshot::*;
 
 #[macro_use]
 extern crate alloc;
 
 #[cfg_attr(test, macro_use)]
-#[cfg(test)]
+#[cfg(any(test, feature = "std"))]
 extern crate std;
@@ -22,9 +22,44 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 use crate::cstr_util::c_str_len_ptr;
-use crate::{AuxVar, AuxVarSerialized, AuxVarType};
-use core::fmt::Debug;
+use crate::{
+    AuxValue, AuxVar, AuxVarFlags, AuxVarSerialized, AuxVarType, EnvEntry, LayoutError,
+    LayoutSnapshot, RelativeLayout, RelativeValue, SnapshotValue,
+};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Write};
+use core::iter::FusedIterator;
 use core::marker::PhantomData;
+use core::mem::size_of;
+
+/// Reads `argc` from the beginning of the data structure without creating an
+/// [`InitialLinuxLibcStackLayout`]. The buffer must be word-aligned and contain at least
+/// one word.
+pub fn peek_argc(bytes: &[u8]) -> Result<usize, LayoutError> {
+    let word = bytes
+        .get(..size_of::<usize>())
+        .ok_or(LayoutError::Truncated)?;
+    if align_offset_for(word) != 0 {
+        return Err(LayoutError::UnalignedBuffer);
+    }
+    Ok(usize::from_ne_bytes(word.try_into().unwrap()))
+}
+
+/// Returns the alignment in bytes, that the beginning of the data structure must have, i.e.,
+/// the alignment of a word.
+pub const fn required_alignment() -> usize {
+    core::mem::align_of::<usize>()
+}
+
+/// Returns the number of bytes to skip at the beginning of `bytes` to reach alignment.
+///
+/// The alignment is given by [`required_alignment`]. This helps to fix a buffer, that was
+/// rejected with [`LayoutError::UnalignedBuffer`], by slicing it. The result may be bigger
+/// than the buffer.
+pub fn align_offset_for(bytes: &[u8]) -> usize {
+    bytes.as_ptr().align_offset(required_alignment())
+}
 
 /// Wrapper around a slice of data, that represents the data structure that Linux passes to the
 /// libc on program startup. Usually this is a struct from `rsp` (stack pointer) to `x`. It is no
@@ -36,20 +71,119 @@ use core::marker::PhantomData;
 /// payload. The data structure is right above the stack. The initial stack pointer points
 /// to `argc`. See <https://lwn.net/Articles/631631/> for more info.
 ///
-/// Instances are created via `InitialLinuxLibcStackLayout::from::<[u8>]`.
+/// Instances are created via `InitialLinuxLibcStackLayout::from::<[u8]>`,
+/// [`InitialLinuxLibcStackLayout::try_new`], or [`InitialLinuxLibcStackLayout::from_raw_parts`].
+/// The data structure must begin at a word-aligned address (see [`required_alignment`]).
 #[derive(Debug)]
 pub struct InitialLinuxLibcStackLayout<'a> {
     bytes: &'a [u8],
+    /// See [`Self::tolerant`].
+    tolerant: bool,
 }
 
 impl<'a> From<&'a [u8]> for InitialLinuxLibcStackLayout<'a> {
-    /// Creates a new [`InitialLinuxLibcStackLayout`].
+    /// Creates a new [`InitialLinuxLibcStackLayout`]. The alignment is not checked, but
+    /// `bytes` must be word-aligned. See [`InitialLinuxLibcStackLayout::try_new`].
     fn from(bytes: &'a [u8]) -> Self {
-        Self { bytes }
+        Self {
+            bytes,
+            tolerant: false,
+        }
     }
 }
 
 impl<'a> InitialLinuxLibcStackLayout<'a> {
+    /// Creates a new [`InitialLinuxLibcStackLayout`] from a raw pointer, e.g., the initial
+    /// stack pointer, that points to `argc`. `len` can be bigger than the actual data
+    /// structure (see [`InitialLinuxLibcStackLayout`]). If `argc` is given, e.g., from the
+    /// `_start`-symbol of a libc, it is checked against the value in the data structure.
+    ///
+    /// # Safety
+    /// `ptr` must be valid for reads of `len` bytes for the lifetime `'a`.
+    ///
+    /// # Panics
+    /// Panics, if `ptr` is not word-aligned (see [`required_alignment`]) or if `argc` doesn't
+    /// match. See [`Self::try_new`] for the non-panicking version.
+    pub unsafe fn from_raw_parts(ptr: *const u8, len: usize, argc: Option<usize>) -> Self {
+        Self::try_new(core::slice::from_raw_parts(ptr, len), argc)
+            .unwrap_or_else(|err| panic!("invalid data structure: {}", err))
+    }
+
+    /// Creates a new [`InitialLinuxLibcStackLayout`] and checks the preconditions instead of
+    /// panicking. `bytes` must begin at a word-aligned address (see [`required_alignment`]),
+    /// otherwise [`LayoutError::UnalignedBuffer`] is returned. This is often not the case for
+    /// a `Vec<u8>` or a memory-mapped file at an offset; use [`align_offset_for`] to fix it.
+    /// If `argc` is given, it is checked against the value in the data structure.
+    pub fn try_new(bytes: &'a [u8], argc: Option<usize>) -> Result<Self, LayoutError> {
+        if align_offset_for(bytes) != 0 {
+            return Err(LayoutError::UnalignedBuffer);
+        }
+        if let Some(expected) = argc {
+            let stored = peek_argc(bytes)?;
+            if stored != expected {
+                return Err(LayoutError::ArgcUnexpected { stored, expected });
+            }
+        }
+        Ok(Self::from(bytes))
+    }
+
+    /// Creates a new [`InitialLinuxLibcStackLayout`] from a data structure that was created
+    /// with [`crate::InitialLinuxLibcStackLayoutBuilder::build_framed`], i.e., with an 8-byte
+    /// little-endian length header. The header must match the length of the remaining bytes
+    /// and the data structure must be word-aligned.
+    pub fn from_framed(bytes: &'a [u8]) -> Result<Self, LayoutError> {
+        const HEADER_SIZE: usize = size_of::<u64>();
+        let actual = bytes.len().saturating_sub(HEADER_SIZE);
+        let header = bytes
+            .get(..HEADER_SIZE)
+            .map(|header| u64::from_le_bytes(header.try_into().unwrap()))
+            .ok_or(LayoutError::FrameLengthMismatch { header: 0, actual })?;
+        if header != actual as u64 {
+            return Err(LayoutError::FrameLengthMismatch { header, actual });
+        }
+        let payload = &bytes[HEADER_SIZE..];
+        if align_offset_for(payload) != 0 {
+            return Err(LayoutError::UnalignedBuffer);
+        }
+        Ok(Self::from(payload))
+    }
+
+    /// Creates a new [`InitialLinuxLibcStackLayout`] from a capture that only contains the
+    /// entries, i.e., `argc` and the `argv`-, `envv`-, and auxv-arrays, but not the data areas
+    /// they reference. The given `argc` must match the stored one and the auxiliary vector must
+    /// be terminated within the buffer. Use [`Self::argv_iter_checked`],
+    /// [`Self::envv_iter_checked`], and [`Self::aux_var_iter_checked`] to access the entries,
+    /// as they don't read beyond the captured bytes.
+    pub fn entries_only(bytes: &'a [u8], argc: usize) -> Result<Self, LayoutError> {
+        if bytes.len() < size_of::<u64>() {
+            return Err(LayoutError::Truncated);
+        }
+        let layout = Self::from(bytes);
+        layout.check_argc_consistency()?;
+        if layout.argc() != argc {
+            return Err(LayoutError::ArgcUnexpected {
+                stored: layout.argc(),
+                expected: argc,
+            });
+        }
+        layout.check_auxv_terminator()?;
+        Ok(layout)
+    }
+
+    /// Maximum number of null words between the `envv`-array and the auxiliary vector that are
+    /// skipped in tolerant mode.
+    const MAX_SKIPPED_NULL_WORDS: usize = 4;
+
+    /// Enables or disables the tolerant mode. Some captured data structures contain additional
+    /// null words between the null pointer that terminates the `envv`-array and the auxiliary
+    /// vector. In tolerant mode, up to four such words are skipped, as long as they can't be the
+    /// beginning of a valid auxiliary vector, i.e., a null word that is followed by a valid
+    /// key. Disabled by default.
+    pub const fn tolerant(mut self, tolerant: bool) -> Self {
+        self.tolerant = tolerant;
+        self
+    }
+
     /// Returns the number of arguments.
     #[allow(clippy::missing_const_for_fn)]
     pub fn argc(&self) -> usize {
@@ -61,6 +195,67 @@ impl<'a> InitialLinuxLibcStackLayout<'a> {
         self.envv_ptr_iter().count()
     }
 
+    /// Returns the bytes of the argument at `index` including the terminating null byte, if
+    /// `index` is smaller than [`Self::argc`]. The `argv`-array is accessed like in
+    /// [`Self::argv_ptrs`].
+    ///
+    /// # Safety
+    /// See [`Self::argv_iter`].
+    pub unsafe fn arg_bytes(&self, index: usize) -> Option<&'a [u8]> {
+        let ptr = *self.argv_ptrs().get(index)? as *const u8;
+        Some(core::slice::from_raw_parts(ptr, c_str_len_ptr(ptr) + 1))
+    }
+
+    /// Collects the arguments into a fixed-size array without a heap allocation. Like
+    /// [`Self::argv_iter`], the strings include their terminating null byte. Returns the
+    /// array, where the first entries are filled, and the number of arguments.
+    ///
+    /// # Safety
+    /// See [`Self::argv_iter`].
+    pub unsafe fn collect_argv_into<const N: usize>(
+        &self,
+    ) -> Result<([Option<&'a str>; N], usize), LayoutError> {
+        let argc = self.argc();
+        if argc > N {
+            return Err(LayoutError::CapacityExceeded {
+                required: argc,
+                capacity: N,
+            });
+        }
+        let mut argv = [None; N];
+        let mut count = 0;
+        for (slot, arg) in argv
+            .iter_mut()
+            .zip(CstrIter::<'a>::new(self.get_argv_ptr()))
+        {
+            *slot = Some(arg);
+            count += 1;
+        }
+        Ok((argv, count))
+    }
+
+    /// Returns the number of bytes that the argument strings occupy, including their null
+    /// bytes. This equals [`crate::InitialLinuxLibcStackLayoutBuilder::argv_data_area_size`]
+    /// of the builder that created the data structure, unless shared strings are counted
+    /// multiple times (see [`crate::InitialLinuxLibcStackLayoutBuilder::dedup_strings`]).
+    ///
+    /// # Safety
+    /// See [`Self::argv_iter`].
+    pub unsafe fn argv_total_bytes(&self) -> usize {
+        self.argv_iter().map(str::len).sum()
+    }
+
+    /// Returns the number of bytes that the environment strings occupy, including their null
+    /// bytes. This equals [`crate::InitialLinuxLibcStackLayoutBuilder::envv_data_area_size`]
+    /// of the builder that created the data structure, unless shared strings are counted
+    /// multiple times (see [`crate::InitialLinuxLibcStackLayoutBuilder::dedup_strings`]).
+    ///
+    /// # Safety
+    /// See [`Self::envv_iter`].
+    pub unsafe fn envv_total_bytes(&self) -> usize {
+        self.envv_iter().map(str::len).sum()
+    }
+
     /// Returns the pointer to the begin of argv array.
     fn get_argv_ptr(&self) -> *const *const u8 {
         // + 1: skip argc
@@ -89,6 +284,53 @@ impl<'a> InitialLinuxLibcStackLayout<'a> {
         }
     }
 
+    /// Returns the program name (`argv[0]`) including the terminating null byte, like
+    /// [`Self::argv_iter`], if there is at least one argument. Only the first entry of the
+    /// `argv`-array is read, and only if it is inside the underlying buffer.
+    ///
+    /// # Safety
+    /// This function produces UB (page fault, seg fault, read invalid memory), if the referenced
+    /// pointer is not valid inside the address space of the caller.
+    pub unsafe fn program_name(&self) -> Option<&'a str> {
+        if self.argc() == 0 {
+            return None;
+        }
+        let c_str_ptr = Self::word_at(self.bytes, size_of::<u64>())? as *const u8;
+        if c_str_ptr.is_null() {
+            return None;
+        }
+        let c_str_bytes = core::slice::from_raw_parts(c_str_ptr, c_str_len_ptr(c_str_ptr) + 1);
+        Some(core::str::from_utf8_unchecked(c_str_bytes))
+    }
+
+    /// Checks that the stored `argc` equals the number of non-null pointers in the
+    /// `argv`-array before its terminating null pointer. A corrupt or malicious `argc`
+    /// results in a wrong location of the `envv`-array and the auxiliary vector. Only the
+    /// underlying buffer is read.
+    pub fn check_argc_consistency(&self) -> Result<(), LayoutError> {
+        let argc = self.argc();
+        let actual = self
+            .bytes
+            .chunks_exact(size_of::<usize>())
+            // skip argc
+            .skip(1)
+            .map(|bytes| usize::from_ne_bytes(bytes.try_into().unwrap()))
+            .take_while(|ptr| *ptr != 0)
+            .count();
+        if argc == actual {
+            Ok(())
+        } else {
+            Err(LayoutError::ArgcMismatch { argc, actual })
+        }
+    }
+
+    /// Returns the byte offset into the buffer, where the parser expects the envp array to
+    /// begin. This is derived from [`Self::argc`] and helps to sanity-check the computed
+    /// boundaries, if argc is corrupt.
+    pub fn envv_offset(&self) -> usize {
+        self.get_envv_ptr() as usize - self.bytes.as_ptr() as usize
+    }
+
     /// Returns the pointer to the beginning of the envp array.
     fn get_envv_ptr(&self) -> *const *const u8 {
         unsafe {
@@ -110,6 +352,41 @@ impl<'a> InitialLinuxLibcStackLayout<'a> {
         CstrIter::new(self.get_envv_ptr())
     }
 
+    /// Iterates over the keys of the environment variables, i.e., the part of each entry
+    /// before the first `=`. Entries without a `=` are returned completely. See
+    /// [`Self::envv_iter`].
+    ///
+    /// # Safety
+    /// This function produces UB (page fault, seg fault, read invalid memory), if the referenced
+    /// pointers are not valid inside the address space of the caller.
+    pub unsafe fn env_keys(&self) -> impl Iterator<Item = &'a str> {
+        CstrIter::<'a>::new(self.get_envv_ptr()).map(|env| {
+            let env = env.strip_suffix('\0').unwrap_or(env);
+            env.split('=').next().unwrap_or(env)
+        })
+    }
+
+    /// Returns owned copies of the environment variables, split into key and value at the
+    /// first `=`. Invalid UTF-8 is replaced with `U+FFFD`. In contrast to [`Self::envv_iter`],
+    /// the result is independent of the lifetime of the underlying buffer.
+    ///
+    /// # Safety
+    /// This function produces UB (page fault, seg fault, read invalid memory), if the referenced
+    /// pointers are not valid inside the address space of the caller.
+    pub unsafe fn env_entries(&self) -> Vec<EnvEntry> {
+        self.envv_ptr_iter()
+            .map(|ptr| {
+                let bytes = core::slice::from_raw_parts(ptr, c_str_len_ptr(ptr));
+                let env = String::from_utf8_lossy(bytes);
+                let (key, value) = env.split_once('=').unwrap_or((&env, ""));
+                EnvEntry {
+                    key: String::from(key),
+                    value: String::from(value),
+                }
+            })
+            .collect()
+    }
+
     /// Iterates only over the pointers to the environment variables. See [`NullTerminatedArrIter`].
     /// This is always memory-safe even if the pointers are created for another address space,
     /// because no pointers are dereference by this iterator.
@@ -119,8 +396,110 @@ impl<'a> InitialLinuxLibcStackLayout<'a> {
         }
     }
 
-    /// Iterates over all entries in the auxiliary vector. See [`AuxVarIter`].
-    /// This is unsafe, because it will result in segfaults/page faults or invalid memory
+    /// Returns the `argv`-array as slice of raw pointers, excluding the terminating null
+    /// pointer. This is memory-safe even if the pointers are created for another address
+    /// space, because no pointers are dereferenced.
+    ///
+    /// # Panics
+    /// Panics, if the array is not properly aligned or exceeds the underlying buffer.
+    pub fn argv_ptrs(&self) -> &'a [usize] {
+        let argc = Self::word_at(self.bytes, 0).expect("pointer array exceeds the buffer");
+        self.ptr_array(size_of::<u64>(), argc)
+    }
+
+    /// Returns the `envv`-array as slice of raw pointers, excluding the terminating null
+    /// pointer. See [`Self::argv_ptrs`].
+    ///
+    /// # Panics
+    /// Panics, if the array is not properly aligned or exceeds the underlying buffer.
+    pub fn envv_ptrs(&self) -> &'a [usize] {
+        // only reads inside the buffer, unlike `Self::envc`
+        let bounds = self.envv_offset_checked().and_then(|offset| {
+            let end = Self::ptr_array_end(self.bytes, offset)?;
+            Some((offset, (end - offset) / size_of::<usize>() - 1))
+        });
+        let (offset, envc) = bounds.expect("pointer array exceeds the buffer");
+        self.ptr_array(offset, envc)
+    }
+
+    /// Helper for [`Self::argv_ptrs`] and [`Self::envv_ptrs`]. `offset` is the byte offset
+    /// of the array in the buffer and `len` the number of pointers.
+    fn ptr_array(&self, offset: usize, len: usize) -> &'a [usize] {
+        let end = len
+            .checked_mul(size_of::<usize>())
+            .and_then(|size| offset.checked_add(size));
+        assert!(
+            matches!(end, Some(end) if end <= self.bytes.len()),
+            "pointer array exceeds the buffer"
+        );
+        let ptr = self.bytes[offset..].as_ptr();
+        assert_eq!(
+            ptr as usize % core::mem::align_of::<usize>(),
+            0,
+            "pointer array must be aligned"
+        );
+        unsafe { core::slice::from_raw_parts(ptr.cast(), len) }
+    }
+
+    /// Memory-safe version of [`Self::argv_iter`]. An argument is only resolved, if it is a
+    /// null-terminated valid UTF-8 C-string inside the underlying buffer. Otherwise, `None` is
+    /// returned for it. This is useful for partial captures (see [`Self::entries_only`]).
+    pub fn argv_iter_checked(&self) -> impl Iterator<Item = Option<&'a str>> {
+        let bytes = self.bytes;
+        self.argv_ptr_iter()
+            .map(move |ptr| Self::resolve_cstr_checked(bytes, ptr))
+    }
+
+    /// Memory-safe version of [`Self::envv_iter`]. See [`Self::argv_iter_checked`].
+    pub fn envv_iter_checked(&self) -> impl Iterator<Item = Option<&'a str>> {
+        let bytes = self.bytes;
+        self.envv_ptr_iter()
+            .map(move |ptr| Self::resolve_cstr_checked(bytes, ptr))
+    }
+
+    /// Resolves the C-string at `ptr` including its null byte, if it is entirely inside
+    /// `bytes` and valid UTF-8.
+    fn resolve_cstr_checked(bytes: &'a [u8], ptr: *const u8) -> Option<&'a str> {
+        let offset = (ptr as usize).wrapping_sub(bytes.as_ptr() as usize);
+        let data = bytes.get(offset..)?;
+        let len = data.iter().position(|b| *b == 0)? + 1;
+        core::str::from_utf8(&data[..len]).ok()
+    }
+
+    /// Memory-safe version of [`Self::aux_var_iter`]. Referenced data of entries, whose value
+    /// lives in the aux data area (see [`AuxVarType::value_in_data_area`]), is only resolved,
+    /// if it is inside the underlying buffer (see [`Self::contains_ptr`]). Otherwise,
+    /// [`LayoutError::PointerOutOfRange`] is returned for the entry. C-strings are only read up
+    /// to the end of the buffer. Hence, this is useful for data structures that were created
+    /// for the address space of the caller.
+    ///
+    /// The entries themselves are read as in [`Self::try_auxv_raw_iter`], i.e., never beyond
+    /// the buffer. Unknown keys, a non-zero value of the terminating entry, and a missing
+    /// terminator are reported as errors instead of panicking.
+    pub fn aux_var_iter_checked(&self) -> impl Iterator<Item = Result<AuxVar<'a>, LayoutError>> {
+        let bytes = self.bytes;
+        Self::try_auxv_raw_iter_at(bytes, self.auxv_offset_checked()).map(move |aux| {
+            let aux = aux?;
+            let key = aux.key();
+            if let Some(var) = AuxVar::from_immediate(key, aux.val()) {
+                return Ok(var);
+            }
+            let offset = (aux.val() as *const u8 as usize).wrapping_sub(bytes.as_ptr() as usize);
+            let data = bytes
+                .get(offset..)
+                .ok_or(LayoutError::PointerOutOfRange(key))?;
+            let data = match key.data_area_val_size_hint() {
+                Some(size) => data
+                    .get(..size)
+                    .ok_or(LayoutError::PointerOutOfRange(key))?,
+                None => data,
+            };
+            AuxVar::from_data_area_bytes(key, data).ok_or(LayoutError::InvalidAuxData(key))
+        })
+    }
+
+    /// Iterates over all entries in the auxiliary vector. See [`AuxVarIter`]. Entries with a
+    /// C-string, that is not valid UTF-8, are skipped. This is unsafe, because it will result in segfaults/page faults or invalid memory
     /// being read, if the pointers are not valid in the address space of the caller.
     ///
     /// # Safety
@@ -130,6 +509,154 @@ impl<'a> InitialLinuxLibcStackLayout<'a> {
         AuxVarIter::new(self.aux_serialized_iter())
     }
 
+    /// Fallible version of [`Self::argv_ptr_iter`], that never panics and only reads the
+    /// underlying buffer. If the buffer ends before the terminating null pointer,
+    /// [`LayoutError::Truncated`] is yielded as last item. Pointers outside of the buffer (see
+    /// [`Self::contains_ptr`]) yield [`LayoutError::DataPointerOutOfRange`]. Hence, the data
+    /// structure must be valid in the address space of the caller. Use
+    /// [`Self::argv_iter_checked`] to resolve the pointers safely. This is useful for untrusted
+    /// input, e.g., when fuzzing.
+    pub fn try_argv_raw_iter(&self) -> impl Iterator<Item = Result<*const u8, LayoutError>> + 'a {
+        Self::try_ptr_array_iter(self.bytes, Some(size_of::<u64>()))
+    }
+
+    /// Fallible version of [`Self::envv_ptr_iter`]. See [`Self::try_argv_raw_iter`]. The
+    /// `envv`-array begins after the terminating null pointer of the `argv`-array.
+    pub fn try_envv_raw_iter(&self) -> impl Iterator<Item = Result<*const u8, LayoutError>> + 'a {
+        let start = Self::ptr_array_end(self.bytes, size_of::<u64>());
+        Self::try_ptr_array_iter(self.bytes, start)
+    }
+
+    /// Fallible version of [`Self::aux_serialized_iter`], that never panics and only reads the
+    /// underlying buffer. The auxiliary vector begins after the terminating null pointer of
+    /// the `envv`-array. The iterator stops after the [`AuxVarType::Null`] entry.
+    ///
+    /// Entries with an unknown key yield [`LayoutError::UnknownAuxVarType`] and are skipped. A
+    /// non-zero value of the terminating entry yields [`LayoutError::NonZeroAtNull`]. If the
+    /// buffer ends before the auxiliary vector begins, [`LayoutError::Truncated`] is yielded.
+    /// If it ends before the terminating entry, [`LayoutError::MissingAuxvTerminator`] is
+    /// yielded as last item. Values that point into the aux data area (see
+    /// [`AuxVarType::value_in_data_area`]) but outside of the buffer yield
+    /// [`LayoutError::PointerOutOfRange`]. Use [`Self::aux_var_iter_checked`] to resolve them
+    /// safely.
+    pub fn try_auxv_raw_iter(
+        &self,
+    ) -> impl Iterator<Item = Result<AuxVarSerialized<'a>, LayoutError>> + 'a {
+        let bytes = self.bytes;
+        let offset = Self::ptr_array_end(bytes, size_of::<u64>())
+            .and_then(|envv_offset| Self::ptr_array_end(bytes, envv_offset));
+        Self::try_auxv_raw_iter_at(bytes, offset).map(move |aux| {
+            let aux = aux?;
+            let ptr = aux.val() as *const u8;
+            if aux.key().value_in_data_area() && !bytes.as_ptr_range().contains(&ptr) {
+                Err(LayoutError::PointerOutOfRange(aux.key()))
+            } else {
+                Ok(aux)
+            }
+        })
+    }
+
+    /// Iterates over the auxiliary vector at `offset` without panicking. See
+    /// [`Self::try_auxv_raw_iter`].
+    fn try_auxv_raw_iter_at(
+        bytes: &'a [u8],
+        offset: Option<usize>,
+    ) -> impl Iterator<Item = Result<AuxVarSerialized<'a>, LayoutError>> + 'a {
+        let mut offset = offset;
+        let mut done = false;
+        core::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let key_offset = match offset {
+                Some(offset) => offset,
+                None => {
+                    done = true;
+                    return Some(Err(LayoutError::Truncated));
+                }
+            };
+            let val_offset = key_offset + size_of::<usize>();
+            let (key, val) = match (
+                Self::word_at(bytes, key_offset),
+                Self::word_at(bytes, val_offset),
+            ) {
+                (Some(key), Some(val)) => (key, val),
+                _ => {
+                    done = true;
+                    return Some(Err(LayoutError::MissingAuxvTerminator));
+                }
+            };
+            offset = Some(key_offset + 2 * size_of::<usize>());
+            let item = match AuxVarType::try_from(key) {
+                Ok(AuxVarType::Null) => {
+                    done = true;
+                    if val == 0 {
+                        Ok(AuxVarSerialized::new(AuxVarType::Null, val))
+                    } else {
+                        Err(LayoutError::NonZeroAtNull(val))
+                    }
+                }
+                Ok(key) => Ok(AuxVarSerialized::new(key, val)),
+                Err(err) => Err(err.into()),
+            };
+            Some(item)
+        })
+    }
+
+    /// Reads the word at `offset`, if it is entirely inside `bytes`.
+    fn word_at(bytes: &[u8], offset: usize) -> Option<usize> {
+        let word = bytes.get(offset..offset.checked_add(size_of::<usize>())?)?;
+        Some(usize::from_ne_bytes(word.try_into().unwrap()))
+    }
+
+    /// Returns the offset after the terminating null pointer of the null-terminated pointer
+    /// array at `offset`, if the terminator is inside `bytes`.
+    fn ptr_array_end(bytes: &[u8], offset: usize) -> Option<usize> {
+        let mut offset = offset;
+        loop {
+            let ptr = Self::word_at(bytes, offset)?;
+            offset += size_of::<usize>();
+            if ptr == 0 {
+                return Some(offset);
+            }
+        }
+    }
+
+    /// Iterates over the null-terminated pointer array at `offset` without panicking. If the
+    /// offset is unknown or the buffer ends before the terminator, [`LayoutError::Truncated`]
+    /// is yielded as last item. Pointers outside of `bytes` yield
+    /// [`LayoutError::DataPointerOutOfRange`].
+    fn try_ptr_array_iter(
+        bytes: &'a [u8],
+        offset: Option<usize>,
+    ) -> impl Iterator<Item = Result<*const u8, LayoutError>> + 'a {
+        let mut offset = offset;
+        let mut done = false;
+        core::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            match offset.and_then(|offset| Self::word_at(bytes, offset)) {
+                None => {
+                    done = true;
+                    Some(Err(LayoutError::Truncated))
+                }
+                Some(0) => {
+                    done = true;
+                    None
+                }
+                Some(ptr) => {
+                    offset = offset.map(|offset| offset + size_of::<usize>());
+                    if bytes.as_ptr_range().contains(&(ptr as *const u8)) {
+                        Some(Ok(ptr as *const u8))
+                    } else {
+                        Some(Err(LayoutError::DataPointerOutOfRange(ptr)))
+                    }
+                }
+            }
+        })
+    }
+
     /// Iterates over all entries in the auxiliary vector. See [`AuxVarSerializedIter`].
     /// This is always memory-safe even if the pointers are created for another address space,
     /// because no pointers are dereference by this iterator.
@@ -137,127 +664,750 @@ impl<'a> InitialLinuxLibcStackLayout<'a> {
         AuxVarSerializedIter::new(self.get_auxv_ptr())
     }
 
-    /// Returns the pointer to the beginning of aux variables.
-    fn get_auxv_ptr(&self) -> *const AuxVarSerialized {
-        unsafe {
-            self.get_envv_ptr()
-                // skip all ENV values
-                .add(self.envv_ptr_iter().count())
-                // final null ptr after the envv (+ 8 bytes)
-                .add(1)
-                .cast()
+    /// Like [`Self::aux_serialized_iter`], but pairs every entry with its byte offset from the
+    /// beginning of the buffer. This is useful to locate entries, e.g., for in-place editing.
+    pub fn auxv_offsets_iter(&self) -> impl Iterator<Item = (usize, AuxVarSerialized<'a>)> {
+        let auxv_offset = self.get_auxv_ptr() as usize - self.bytes.as_ptr() as usize;
+        AuxVarSerializedIter::<'a>::new(self.get_auxv_ptr().cast())
+            .enumerate()
+            .map(move |(i, aux)| (auxv_offset + i * size_of::<AuxVarSerialized>(), aux))
+    }
+
+    /// Like [`Self::aux_serialized_iter`], but skips the sanity checks for every entry, i.e.,
+    /// the check for a null pointer and the check that [`AuxVarType::Null`] has a zero value.
+    /// This is faster, if many data structures must be parsed.
+    ///
+    /// # Safety
+    /// The underlying buffer must contain a valid auxiliary vector that is terminated by
+    /// [`AuxVarType::Null`]. Otherwise, the iterator reads beyond the buffer. Unknown keys
+    /// result in an invalid [`AuxVarType`]. Both is UB.
+    #[cfg(feature = "trusted-input")]
+    pub unsafe fn aux_serialized_iter_unchecked(&self) -> AuxVarSerializedUncheckedIter<'a> {
+        AuxVarSerializedUncheckedIter {
+            ptr: self.get_auxv_ptr().cast(),
+            done: false,
         }
     }
-}
 
-/// Iterator that iterates over an array of pointers, that is terminated by a null pointer.
-/// Useful to find all entries of a typical C-string array.
-/// It only returns the pointer itself but doesn't dereferences the data.
-#[derive(Debug)]
-pub struct NullTerminatedArrIter {
-    ptr: *const *const u8,
-}
+    /// High-level version of [`Self::aux_serialized_iter_unchecked`]. See [`Self::aux_var_iter`].
+    /// Entries with a C-string, that is not valid UTF-8, are skipped.
+    ///
+    /// # Safety
+    /// The requirements of [`Self::aux_serialized_iter_unchecked`] and of
+    /// [`Self::aux_var_iter`] must be fulfilled.
+    #[cfg(feature = "trusted-input")]
+    pub unsafe fn aux_var_iter_unchecked(&self) -> impl Iterator<Item = AuxVar<'a>> {
+        self.aux_serialized_iter_unchecked()
+            .filter_map(|ref x| AuxVar::from_serialized(x).ok())
+    }
 
-impl Iterator for NullTerminatedArrIter {
-    type Item = *const u8;
+    /// Collects all entries of the auxiliary vector, including the terminating
+    /// [`AuxVarType::Null`] entry. See [`Self::aux_serialized_iter`]. This is memory-safe even
+    /// if the pointers are created for another address space. The entries are bound to the
+    /// lifetime of the underlying buffer rather than to the lifetime of `self`.
+    pub fn aux_serialized_vec(&self) -> Vec<AuxVarSerialized<'a>> {
+        AuxVarSerializedIter::<'a>::new(self.get_auxv_ptr().cast()).collect()
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if unsafe { (*self.ptr).is_null() } {
-            None
-        } else {
-            let c_str_ptr = unsafe { *self.ptr };
-            // + 8 bytes: to next array entry
-            self.ptr = unsafe { self.ptr.add(1) };
-            Some(c_str_ptr)
+    /// Creates an owned copy of the data structure. See [`LayoutSnapshot`]. C-strings of the
+    /// auxiliary vector, that are not valid UTF-8, are converted lossily.
+    ///
+    /// # Safety
+    /// This function produces UB (page fault, seg fault, read invalid memory), if the referenced
+    /// pointers are not valid inside the address space of the caller.
+    pub unsafe fn snapshot(&self) -> LayoutSnapshot {
+        let to_string = |cstr: &str| String::from(cstr.strip_suffix('\0').unwrap_or(cstr));
+        LayoutSnapshot {
+            argc: self.argc(),
+            argv: self.argv_iter().map(to_string).collect(),
+            envv: self.envv_iter().map(to_string).collect(),
+            auxv: self
+                .aux_serialized_iter()
+                .map(|aux| {
+                    let val = AuxVar::from_serialized(&aux).map_or_else(
+                        |_| SnapshotValue::String(String::from_utf8_lossy(aux.cstr_bytes()).into()),
+                        |var| SnapshotValue::from(&var),
+                    );
+                    (aux.key(), val)
+                })
+                .collect(),
         }
     }
-}
 
-/// Iterator that iterates over an array of null terminated C-strings.
-#[derive(Debug)]
-pub struct CstrIter<'a> {
-    arr_iter: NullTerminatedArrIter,
-    _marker: PhantomData<&'a ()>,
-}
+    /// Returns a hash of the logical content, that is independent of the address of the data
+    /// structure. See [`LayoutSnapshot::content_hash`].
+    ///
+    /// # Safety
+    /// This function produces UB (page fault, seg fault, read invalid memory), if the referenced
+    /// pointers are not valid inside the address space of the caller.
+    pub unsafe fn content_hash(&self) -> u64 {
+        self.snapshot().content_hash()
+    }
 
-impl<'a> CstrIter<'a> {
-    unsafe fn new(ptr: *const *const u8) -> Self {
-        Self {
-            arr_iter: NullTerminatedArrIter { ptr },
-            _marker: PhantomData::default(),
-        }
+    /// Returns the base address of the vDSO, i.e., the value of
+    /// [`AuxVarType::SysinfoEhdr`], if present. This is memory-safe, because the pointer is
+    /// only reported but never dereferenced.
+    pub fn vdso_base(&self) -> Option<*const u8> {
+        self.find_aux_val(AuxVarType::SysinfoEhdr)
+            .map(|val| val as *const u8)
     }
-}
 
-impl<'a> Iterator for CstrIter<'a> {
-    type Item = &'a str;
+    /// Returns the value of [`AuxVarType::Sysinfo`], if present. This is the entry point of
+    /// the system call function in the vDSO, which only exists on some architectures. This is
+    /// memory-safe, because the pointer is only reported but never dereferenced.
+    pub fn sysinfo(&self) -> Option<*const u8> {
+        self.find_aux_val(AuxVarType::Sysinfo)
+            .map(|val| val as *const u8)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.arr_iter.next().map(|c_str_ptr| {
-            // + null byte
-            let c_str_bytes =
-                unsafe { core::slice::from_raw_parts(c_str_ptr, c_str_len_ptr(c_str_ptr) + 1) };
-            unsafe { core::str::from_utf8_unchecked(c_str_bytes) }
-        })
+    /// Returns true, if [`AuxVarType::Flags`] contains [`AuxVarFlags::PRESERVE_ARGV0`]. In that
+    /// case, the program interpreter (e.g., `binfmt_misc`) preserved the original `argv[0]`.
+    /// This is memory-safe, because no pointers are dereferenced.
+    pub fn preserves_argv0(&self) -> bool {
+        matches!(
+            self.find_aux_val(AuxVarType::Flags),
+            Some(flags) if flags & AuxVarFlags::PRESERVE_ARGV0.bits() != 0
+        )
     }
-}
 
-/// Iterator over all serialized entries in the auxiliary vector.
-/// This is memory-safe, even if the pointers are for another address space, because
-/// no pointers are dereferenced.
-#[derive(Debug)]
-pub struct AuxVarSerializedIter<'a> {
-    ptr: *const AuxVarSerialized<'a>,
-    done: bool,
-    _marker: PhantomData<&'a ()>,
-}
+    /// Returns the address of the random bytes of [`AuxVarType::Random`] in the address space
+    /// of the target, if present. In contrast to [`AuxVar::Random`], the bytes are not
+    /// dereferenced. Hence, this is memory-safe for data structures of other address spaces.
+    pub fn random_address(&self) -> Option<usize> {
+        self.find_aux_val(AuxVarType::Random)
+    }
 
-impl<'a> AuxVarSerializedIter<'a> {
-    fn new(ptr: *const AuxVarSerialized<'a>) -> Self {
-        Self {
-            ptr,
-            done: false,
-            _marker: PhantomData::default(),
+    /// Returns the C-string of [`AuxVarType::Platform`] without the terminating null byte,
+    /// if it is present and lies within the underlying buffer (see [`Self::contains_ptr`]).
+    /// This is memory-safe and useful for data structures that were created for the address
+    /// space of the caller, e.g., with [`crate::InitialLinuxLibcStackLayoutBuilder::build_ref`].
+    pub fn platform_checked(&self) -> Option<&'a str> {
+        self.find_aux_cstr_checked(AuxVarType::Platform)
+    }
+
+    /// Returns the C-string of [`AuxVarType::ExecFn`] without the terminating null byte. See
+    /// [`Self::platform_checked`].
+    pub fn execfn_checked(&self) -> Option<&'a str> {
+        self.find_aux_cstr_checked(AuxVarType::ExecFn)
+    }
+
+    /// Returns the C-string of the first aux entry with the given key without the terminating
+    /// null byte, if it lies within the underlying buffer.
+    fn find_aux_cstr_checked(&self, key: AuxVarType) -> Option<&'a str> {
+        let ptr = self.find_aux_val(key)? as *const u8;
+        if !self.contains_ptr(ptr) {
+            return None;
         }
+        Self::resolve_cstr_checked(self.bytes, ptr).map(|cstr| cstr.trim_end_matches('\0'))
     }
-}
 
-impl<'a> Iterator for AuxVarSerializedIter<'a> {
-    type Item = AuxVarSerialized<'a>;
+    /// Returns the raw value of the first aux entry with the given key.
+    fn find_aux_val(&self, key: AuxVarType) -> Option<usize> {
+        self.aux_raw_pairs()
+            .take_while(|(raw_key, _)| *raw_key != AuxVarType::Null.val())
+            .find(|(raw_key, _)| *raw_key == key.val())
+            .map(|(_, val)| val)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.done {
-            None
-        } else {
-            let aux_var_ser = unsafe { self.ptr.as_ref().unwrap() };
-            if aux_var_ser.key() == AuxVarType::Null {
-                if aux_var_ser.val() != 0 {
-                    panic!(
-                        "val of end key is not null but {}! Probably read wrong memory!",
-                        aux_var_ser.val()
-                    );
+    /// Returns the pointer to the beginning of aux variables.
+    fn get_auxv_ptr(&self) -> *const AuxVarSerialized {
+        let mut ptr = unsafe {
+            self.get_envv_ptr()
+                // skip all ENV values
+                .add(self.envv_ptr_iter().count())
+                // final null ptr after the envv (+ 8 bytes)
+                .add(1)
+                .cast::<usize>()
+        };
+        if self.tolerant {
+            let end = self.bytes.as_ptr_range().end as usize;
+            for _ in 0..Self::MAX_SKIPPED_NULL_WORDS {
+                if ptr as usize + 2 * size_of::<usize>() > end {
+                    break;
+                }
+                // a terminating null entry must have a null value; a null key followed by
+                // a valid key is a spurious null word
+                let (key, val) = unsafe { (*ptr, *ptr.add(1)) };
+                if key == 0 && val != 0 && AuxVarType::try_from(val).is_ok() {
+                    ptr = unsafe { ptr.add(1) };
+                } else {
+                    break;
                 }
-                self.done = true;
             }
-
-            self.ptr = unsafe { self.ptr.add(1) };
-
-            Some(*aux_var_ser)
         }
+        ptr.cast()
     }
-}
 
-/// Iterator over all serialized entries in the auxiliary vector.
-/// This is a high-level version of [`AuxVarSerializedIter`] but unsafe,
-/// if the pointers are not valid in the address space of the caller.
-#[derive(Debug)]
-pub struct AuxVarIter<'a> {
-    serialized_iter: AuxVarSerializedIter<'a>,
-}
+    /// Returns the terminating [`AuxVarType::Null`] entry of the auxiliary vector including its
+    /// value, which should be zero, if it is found within the buffer. In contrast to
+    /// [`Self::aux_serialized_iter`], this never panics and only reads the underlying buffer,
+    /// even if it ends inside the `argv`- or `envv`-array.
+    pub fn auxv_terminator(&self) -> Option<AuxVarSerialized<'a>> {
+        self.aux_raw_pairs()
+            .find(|(key, _)| *key == AuxVarType::Null.val())
+            .map(|(_, val)| AuxVarSerialized::new(AuxVarType::Null, val))
+    }
 
-impl<'a> AuxVarIter<'a> {
-    const fn new(serialized_iter: AuxVarSerializedIter<'a>) -> Self {
-        Self { serialized_iter }
+    /// Returns the keys of the auxiliary vector that are not known by [`AuxVarType`], e.g.,
+    /// types of newer kernels. In contrast to [`Self::aux_serialized_iter`], this never panics
+    /// and only reads the underlying buffer.
+    pub fn unknown_auxv_types(&self) -> Vec<usize> {
+        self.aux_raw_pairs()
+            .map(|(key, _)| key)
+            .take_while(|key| *key != AuxVarType::Null.val())
+            .filter(|key| AuxVarType::try_from(*key).is_err())
+            .collect()
+    }
+
+    /// Returns the byte offset of the `envv`-array as derived from [`Self::argc`], if `argc`
+    /// is within the buffer and the computation doesn't overflow.
+    fn envv_offset_checked(&self) -> Option<usize> {
+        let argc = Self::word_at(self.bytes, 0)?;
+        // argc, the argv-array and its terminating null pointer
+        argc.checked_add(1)?
+            .checked_mul(size_of::<usize>())?
+            .checked_add(size_of::<u64>())
+    }
+
+    /// Returns the byte offset of the auxiliary vector, if the `envv`-array is terminated within
+    /// the buffer. In contrast to [`Self::get_auxv_ptr`], this only reads the underlying buffer
+    /// and the computation can't overflow. Spurious null words are skipped in tolerant mode.
+    fn auxv_offset_checked(&self) -> Option<usize> {
+        let mut offset = Self::ptr_array_end(self.bytes, self.envv_offset_checked()?)?;
+        if self.tolerant {
+            for _ in 0..Self::MAX_SKIPPED_NULL_WORDS {
+                let key = Self::word_at(self.bytes, offset);
+                let val = Self::word_at(self.bytes, offset + size_of::<usize>());
+                // same rule as in `get_auxv_ptr`
+                match (key, val) {
+                    (Some(0), Some(val)) if val != 0 && AuxVarType::try_from(val).is_ok() => {
+                        offset += size_of::<usize>();
+                    }
+                    _ => break,
+                }
+            }
+        }
+        Some(offset)
+    }
+
+    /// Iterates over the raw `(key, value)`-pairs from the beginning of the auxiliary vector
+    /// to the end of the buffer. It doesn't stop at the terminating [`AuxVarType::Null`] entry.
+    /// Yields nothing, if the beginning of the auxiliary vector is not within the buffer.
+    fn aux_raw_pairs(&self) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.auxv_offset_checked()
+            .and_then(|auxv_offset| self.bytes.get(auxv_offset..))
+            .unwrap_or(&[])
+            .chunks_exact(2 * size_of::<usize>())
+            .map(|pair| {
+                let (key, val) = pair.split_at(size_of::<usize>());
+                (
+                    usize::from_ne_bytes(key.try_into().unwrap()),
+                    usize::from_ne_bytes(val.try_into().unwrap()),
+                )
+            })
+    }
+
+    /// Checks that the auxiliary vector is terminated by an [`AuxVarType::Null`] entry within
+    /// the buffer and that the value of this entry is zero. See [`Self::auxv_terminator`].
+    pub fn check_auxv_terminator(&self) -> Result<(), LayoutError> {
+        let val = self
+            .auxv_terminator()
+            .ok_or(LayoutError::MissingAuxvTerminator)?
+            .val();
+        if val == 0 {
+            Ok(())
+        } else {
+            Err(LayoutError::NonZeroAtNull(val))
+        }
+    }
+
+    /// Returns true, if the auxiliary vector has a valid terminating [`AuxVarType::Null`] entry
+    /// within the buffer. This is a cheap precondition before using the iterators that panic
+    /// on a missing terminator, such as [`Self::aux_serialized_iter`]. See
+    /// [`Self::check_auxv_terminator`].
+    pub fn has_terminator(&self) -> bool {
+        self.check_auxv_terminator().is_ok()
+    }
+
+    /// Returns the number of bytes from the beginning of the buffer to the end of the data
+    /// structure. This is the end of the last data area, that is referenced by `argv`, `envv`,
+    /// or the auxiliary vector, but at least the end of the auxiliary vector itself. This is
+    /// useful to trim an oversized buffer to the actual size of the data structure.
+    ///
+    /// Only pointers that point into the underlying buffer are taken into account (see
+    /// [`Self::contains_ptr`]). A trailing null word above the data areas is not included.
+    ///
+    /// # Safety
+    /// This function produces UB (page fault, seg fault, read invalid memory), if the referenced
+    /// C-strings are not null-terminated inside the buffer.
+    pub unsafe fn actual_len(&self) -> usize {
+        let cstr_end = |ptr: *const u8| ptr as usize + c_str_len_ptr(ptr) + 1;
+
+        let args_and_envs = self
+            .argv_ptr_iter()
+            .chain(self.envv_ptr_iter())
+            .filter(|ptr| self.contains_ptr(*ptr))
+            .map(cstr_end);
+        let aux_data = self
+            .aux_serialized_iter()
+            .filter(|aux| aux.key().value_in_data_area())
+            .filter(|aux| self.contains_ptr(aux.val() as *const u8))
+            .map(|aux| {
+                aux.key()
+                    .data_area_val_size_hint()
+                    .map_or_else(|| cstr_end(aux.val() as *const u8), |size| aux.val() + size)
+            });
+        let auxv_end = self.get_auxv_ptr().add(self.aux_serialized_iter().count()) as usize;
+
+        let end = args_and_envs.chain(aux_data).fold(auxv_end, usize::max);
+        end - self.bytes.as_ptr() as usize
+    }
+
+    /// Formats the auxiliary vector as table with aligned columns for the name of the type
+    /// (see [`AuxVarType::name`]), its numeric value, and the value of the entry. The first
+    /// line is a header. Useful for CLI output.
+    ///
+    /// # Safety
+    /// See [`Self::aux_var_iter`].
+    pub unsafe fn auxv_table(&self) -> String {
+        let mut table = String::new();
+        let _ = writeln!(table, "{:<20} {:>4}  VALUE", "TYPE", "CODE");
+        for aux in self.aux_var_iter() {
+            let key = aux.key();
+            let _ = write!(table, "{:<20} {:>4}  ", key.name(), key.val());
+            let _ = match aux.value() {
+                AuxValue::Int(val) => writeln!(table, "{}", val),
+                AuxValue::Bool(val) => writeln!(table, "{}", val),
+                AuxValue::Flags(flags) => writeln!(table, "{:?}", flags),
+                AuxValue::Ptr(ptr) => writeln!(table, "{:?}", ptr),
+                AuxValue::Str(val) => writeln!(table, "{:?}", val.trim_end_matches('\0')),
+                AuxValue::Random(bytes) => writeln!(table, "{:02x?}", bytes),
+                AuxValue::None => writeln!(table, "-"),
+            };
+        }
+        table
+    }
+
+    /// Compares the structure and the content of two data structures, but ignores absolute
+    /// addresses. This compares argc, the content of the `argv` and `envv` strings, and the
+    /// entries of the auxiliary vector independent of their order. For entries that hold a
+    /// pointer ([`AuxVar::value_ptr`]) and for [`AuxVarType::Random`], only the presence is
+    /// compared. This is useful to compare a data structure of the builder with one that
+    /// Linux created.
+    ///
+    /// # Safety
+    /// See [`Self::argv_iter`], [`Self::envv_iter`], and [`Self::aux_var_iter`]. This
+    /// applies to both data structures.
+    pub unsafe fn structurally_eq(&self, other: &Self) -> bool {
+        unsafe fn auxv<'b>(layout: &'b InitialLinuxLibcStackLayout) -> Vec<AuxVar<'b>> {
+            let mut auxv = layout.aux_var_iter().collect::<Vec<_>>();
+            auxv.sort();
+            auxv
+        }
+        let aux_eq = |a: &AuxVar, b: &AuxVar| {
+            if a.value_ptr().is_some() || a.key() == AuxVarType::Random {
+                a.key() == b.key()
+            } else {
+                a.eq_content(b)
+            }
+        };
+        let (auxv, other_auxv) = (auxv(self), auxv(other));
+        self.argc() == other.argc()
+            && self.argv_iter().eq(other.argv_iter())
+            && self.envv_iter().eq(other.envv_iter())
+            && auxv.len() == other_auxv.len()
+            && auxv.iter().zip(&other_auxv).all(|(a, b)| aux_eq(a, b))
+    }
+
+    /// Reports all `argv`-, `envv`-, and aux pointers into the data areas as offsets.
+    ///
+    /// The offsets are relative to `assumed_base`, e.g., the assumed address of the data
+    /// structure in the address space it was created for. Values of other aux entries are
+    /// reported unchanged. This is memory-safe, because no pointers are dereferenced.
+    pub fn relative_view(&self, assumed_base: usize) -> RelativeLayout {
+        let offset = |ptr: usize| ptr.wrapping_sub(assumed_base) as isize;
+        RelativeLayout {
+            argv: self
+                .argv_ptr_iter()
+                .map(|ptr| offset(ptr as usize))
+                .collect(),
+            envv: self
+                .envv_ptr_iter()
+                .map(|ptr| offset(ptr as usize))
+                .collect(),
+            auxv: self
+                .aux_serialized_iter()
+                .map(|aux| {
+                    let value = if aux.key().value_in_data_area() {
+                        RelativeValue::Offset(offset(aux.val()))
+                    } else {
+                        RelativeValue::Immediate(aux.val())
+                    };
+                    (aux.key(), value)
+                })
+                .collect(),
+        }
+    }
+
+    /// Copies the data structure ([`Self::actual_len`] bytes) byte-exact into a new buffer and
+    /// relocates it to `new_base`. All `argv`-, `envv`-, and aux pointers into the underlying
+    /// buffer (see [`Self::contains_ptr`]) are rebased. Other pointers, the order of the
+    /// entries, and the padding stay untouched. Unlike a round-trip through
+    /// [`crate::InitialLinuxLibcStackLayoutBuilder`], nothing is normalized.
+    ///
+    /// # Safety
+    /// See [`Self::actual_len`].
+    pub unsafe fn reemit(&self, new_base: usize) -> Vec<u8> {
+        const WORD_SIZE: usize = size_of::<usize>();
+        let base = self.bytes.as_ptr() as usize;
+        let mut buf = self.bytes[..self.actual_len()].to_vec();
+
+        let argv_slots = (0..self.argv_ptr_iter().count())
+            .map(|i| self.get_argv_ptr() as usize - base + i * WORD_SIZE);
+        let envv_slots =
+            (0..self.envv_ptr_iter().count()).map(|i| self.envv_offset() + i * WORD_SIZE);
+        let auxv_offset = self.get_auxv_ptr() as usize - base;
+        let aux_slots = self
+            .aux_serialized_iter()
+            .enumerate()
+            .filter(|(_, aux)| aux.key().value_in_data_area())
+            .map(|(i, _)| auxv_offset + i * size_of::<AuxVarSerialized>() + WORD_SIZE);
+
+        for slot in argv_slots.chain(envv_slots).chain(aux_slots) {
+            let bytes = &mut buf[slot..slot + WORD_SIZE];
+            let ptr = usize::from_ne_bytes(bytes.try_into().unwrap());
+            if self.contains_ptr(ptr as *const u8) {
+                bytes.copy_from_slice(&(ptr - base + new_base).to_ne_bytes());
+            }
+        }
+        buf
+    }
+
+    /// Reports runs of zero bytes between the data that the `argv`-, `envv`-, and aux
+    /// pointers reference, as `(offset, length)`-pairs. This covers the padding that aligns the
+    /// data areas, the random gap of Linux, and the zero padding that aligns the stack. Only
+    /// the range from the end of the auxiliary vector to the end of the last referenced data is
+    /// taken into account. The terminating null bytes of C-strings are part of the data.
+    ///
+    /// Only pointers into the underlying buffer are considered (see [`Self::contains_ptr`])
+    /// and only the underlying buffer is read.
+    pub fn detect_alignment_gaps(&self) -> Vec<(usize, usize)> {
+        let mut gaps = Vec::new();
+        let mut pos = self.auxv_end_offset();
+        for (offset, len) in self.data_regions() {
+            let mut gap_begin = None;
+            for i in pos..offset {
+                match (self.bytes[i], gap_begin) {
+                    (0, None) => gap_begin = Some(i),
+                    (0, Some(_)) => {}
+                    (_, Some(begin)) => {
+                        gaps.push((begin, i - begin));
+                        gap_begin = None;
+                    }
+                    (_, None) => {}
+                }
+            }
+            if let Some(begin) = gap_begin {
+                gaps.push((begin, offset - begin));
+            }
+            pos = pos.max(offset + len);
+        }
+        gaps
+    }
+
+    /// Estimates the randomized gap that Linux inserts to randomize the stack, i.e., the
+    /// number of bytes between the end of the referenced data and `stack_top`. The end of the
+    /// data is determined as in [`Self::detect_alignment_gaps`], but at least the end of the
+    /// auxiliary vector. Returns `None`, if `stack_top` is below the end of the data.
+    ///
+    /// This is only meaningful for real kernel captures, that are parsed at the address where
+    /// the kernel placed them, and with the stack top of the process.
+    pub fn estimate_stack_gap(&self, stack_top: usize) -> Option<usize> {
+        let end = self
+            .data_regions()
+            .into_iter()
+            .map(|(offset, len)| offset + len)
+            .fold(self.auxv_end_offset(), usize::max);
+        stack_top.checked_sub(self.bytes.as_ptr() as usize + end)
+    }
+
+    /// Returns the `(offset, length)`-pairs of all data in the underlying buffer that is
+    /// referenced by the `argv`-, `envv`-, and aux pointers, sorted by offset.
+    fn data_regions(&self) -> Vec<(usize, usize)> {
+        let offset_of = |ptr: *const u8| ptr as usize - self.bytes.as_ptr() as usize;
+        let cstr_region = |offset: usize| {
+            let len = self.bytes[offset..]
+                .iter()
+                .position(|b| *b == 0)
+                .map_or(self.bytes.len() - offset, |len| len + 1);
+            (offset, len)
+        };
+
+        let args_and_envs = self
+            .argv_ptr_iter()
+            .chain(self.envv_ptr_iter())
+            .filter(|ptr| self.contains_ptr(*ptr))
+            .map(|ptr| cstr_region(offset_of(ptr)));
+        let aux_data = self
+            .aux_serialized_iter()
+            .filter(|aux| aux.key().value_in_data_area())
+            .filter(|aux| self.contains_ptr(aux.val() as *const u8))
+            .map(|aux| {
+                let offset = offset_of(aux.val() as *const u8);
+                aux.key()
+                    .data_area_val_size_hint()
+                    .map_or_else(|| cstr_region(offset), |size| (offset, size))
+            });
+        let mut regions = args_and_envs.chain(aux_data).collect::<Vec<_>>();
+        regions.sort_unstable();
+        regions
+    }
+
+    /// Returns the offset of the end of the auxiliary vector, including the terminating
+    /// [`AuxVarType::Null`] entry.
+    fn auxv_end_offset(&self) -> usize {
+        self.get_auxv_ptr() as usize - self.bytes.as_ptr() as usize
+            + self.aux_serialized_iter().count() * size_of::<AuxVarSerialized>()
+    }
+
+    /// Guesses, whether the data structure was created by
+    /// [`crate::InitialLinuxLibcStackLayoutBuilder`] or by Linux (or something that works
+    /// similar). This is a best-effort heuristic for debugging purposes, that only looks at the
+    /// pointer values but doesn't dereference them.
+    ///
+    /// The builder aligns the args data area to a 16 byte boundary and writes the aux data in
+    /// the order of the [`AuxVarType`]s. Linux doesn't align the C-strings of the args and
+    /// writes the random bytes below the platform string.
+    pub fn layout_flavor(&self) -> LayoutFlavor {
+        let mut aux_data = self
+            .aux_serialized_iter()
+            .filter(|aux| aux.key().value_in_data_area())
+            .filter(|aux| aux.key() != AuxVarType::ExecFn)
+            .map(|aux| (aux.key(), aux.val()))
+            .collect::<Vec<_>>();
+        aux_data.sort_unstable();
+        let first_arg_or_env = self
+            .argv_ptr_iter()
+            .chain(self.envv_ptr_iter())
+            .map(|ptr| ptr as usize)
+            .next();
+
+        let first_arg_or_env = match first_arg_or_env {
+            Some(ptr) => ptr,
+            None => return LayoutFlavor::Unknown,
+        };
+        // the aux data area is below the args and env vars in both layouts
+        if aux_data.iter().any(|(_, ptr)| *ptr >= first_arg_or_env) {
+            return LayoutFlavor::Unknown;
+        }
+
+        let aux_data_in_type_order = aux_data.windows(2).all(|pair| pair[0].1 < pair[1].1);
+        if first_arg_or_env % 16 == 0 && aux_data_in_type_order {
+            LayoutFlavor::CrateBuilt
+        } else {
+            LayoutFlavor::KernelLike
+        }
+    }
+
+    /// Returns true, if the pointer points into the underlying buffer. This is only the case
+    /// for data structures that were created for the address space of the caller.
+    pub fn contains_ptr(&self, ptr: *const u8) -> bool {
+        self.bytes.as_ptr_range().contains(&ptr)
+    }
+
+    /// Checks that the pointers into the data areas are in ascending order, as it is the case
+    /// for data structures created by Linux or by [`crate::InitialLinuxLibcStackLayoutBuilder`].
+    /// A violation is a hint for a corrupt or manipulated data structure. Only pointers that
+    /// point into the underlying buffer are taken into account (see [`Self::contains_ptr`]).
+    ///
+    /// The `argv` pointers followed by the `envv` pointers must be non-decreasing. The pointers
+    /// of the auxiliary vector must be non-decreasing among themselves, except for
+    /// [`AuxVarType::ExecFn`], which lives in a dedicated area at the very end of the structure.
+    pub fn check_monotonic_data_pointers(&self) -> Result<(), LayoutError> {
+        let args_and_envs = self.argv_ptr_iter().chain(self.envv_ptr_iter());
+        let aux_data = self
+            .aux_serialized_iter()
+            .filter(|aux| aux.key().value_in_data_area())
+            .filter(|aux| aux.key() != AuxVarType::ExecFn)
+            .map(|aux| aux.val() as *const u8);
+
+        Self::check_monotonic(args_and_envs.filter(|ptr| self.contains_ptr(*ptr)))?;
+        Self::check_monotonic(aux_data.filter(|ptr| self.contains_ptr(*ptr)))
+    }
+
+    /// Helper for [`Self::check_monotonic_data_pointers`].
+    fn check_monotonic(ptrs: impl Iterator<Item = *const u8>) -> Result<(), LayoutError> {
+        let mut previous = 0;
+        for ptr in ptrs.map(|ptr| ptr as usize) {
+            if ptr < previous {
+                return Err(LayoutError::NonMonotonicDataPointer {
+                    previous,
+                    current: ptr,
+                });
+            }
+            previous = ptr;
+        }
+        Ok(())
+    }
+}
+
+/// Result of [`InitialLinuxLibcStackLayout::layout_flavor`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LayoutFlavor {
+    /// The data structure was probably created by [`crate::InitialLinuxLibcStackLayoutBuilder`].
+    CrateBuilt,
+    /// The data structure was probably created by Linux.
+    KernelLike,
+    /// The data structure doesn't look like any of the known layouts or there are not enough
+    /// data pointers to tell the difference.
+    Unknown,
+}
+
+/// Iterator that iterates over an array of pointers, that is terminated by a null pointer.
+/// Useful to find all entries of a typical C-string array.
+/// It only returns the pointer itself but doesn't dereferences the data.
+#[derive(Debug)]
+pub struct NullTerminatedArrIter {
+    ptr: *const *const u8,
+}
+
+impl Iterator for NullTerminatedArrIter {
+    type Item = *const u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if unsafe { (*self.ptr).is_null() } {
+            None
+        } else {
+            let c_str_ptr = unsafe { *self.ptr };
+            // + 8 bytes: to next array entry
+            self.ptr = unsafe { self.ptr.add(1) };
+            Some(c_str_ptr)
+        }
+    }
+}
+
+// stays at the terminating null pointer
+impl FusedIterator for NullTerminatedArrIter {}
+
+/// Iterator that iterates over an array of null terminated C-strings.
+#[derive(Debug)]
+pub struct CstrIter<'a> {
+    arr_iter: NullTerminatedArrIter,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a> CstrIter<'a> {
+    unsafe fn new(ptr: *const *const u8) -> Self {
+        Self {
+            arr_iter: NullTerminatedArrIter { ptr },
+            _marker: PhantomData::default(),
+        }
+    }
+}
+
+impl<'a> Iterator for CstrIter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.arr_iter.next().map(|c_str_ptr| {
+            // + null byte
+            let c_str_bytes =
+                unsafe { core::slice::from_raw_parts(c_str_ptr, c_str_len_ptr(c_str_ptr) + 1) };
+            unsafe { core::str::from_utf8_unchecked(c_str_bytes) }
+        })
+    }
+}
+
+impl<'a> FusedIterator for CstrIter<'a> {}
+
+/// Iterator over all serialized entries in the auxiliary vector.
+/// This is memory-safe, even if the pointers are for another address space, because
+/// no pointers are dereferenced.
+#[derive(Debug)]
+pub struct AuxVarSerializedIter<'a> {
+    ptr: *const AuxVarSerialized<'a>,
+    done: bool,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a> AuxVarSerializedIter<'a> {
+    fn new(ptr: *const AuxVarSerialized<'a>) -> Self {
+        Self {
+            ptr,
+            done: false,
+            _marker: PhantomData::default(),
+        }
+    }
+}
+
+impl<'a> Iterator for AuxVarSerializedIter<'a> {
+    type Item = AuxVarSerialized<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            None
+        } else {
+            let aux_var_ser = unsafe { self.ptr.as_ref().unwrap() };
+            if aux_var_ser.key() == AuxVarType::Null {
+                if aux_var_ser.val() != 0 {
+                    panic!(
+                        "val of end key is not null but {}! Probably read wrong memory!",
+                        aux_var_ser.val()
+                    );
+                }
+                self.done = true;
+            }
+
+            self.ptr = unsafe { self.ptr.add(1) };
+
+            Some(*aux_var_ser)
+        }
+    }
+}
+
+impl<'a> FusedIterator for AuxVarSerializedIter<'a> {}
+
+/// Iterator over all serialized entries in the auxiliary vector without any sanity checks.
+/// See [`InitialLinuxLibcStackLayout::aux_serialized_iter_unchecked`].
+#[cfg(feature = "trusted-input")]
+#[derive(Debug)]
+pub struct AuxVarSerializedUncheckedIter<'a> {
+    ptr: *const AuxVarSerialized<'a>,
+    done: bool,
+}
+
+#[cfg(feature = "trusted-input")]
+impl<'a> Iterator for AuxVarSerializedUncheckedIter<'a> {
+    type Item = AuxVarSerialized<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        // safety guaranteed by the caller of the constructor
+        let aux_var_ser = unsafe { self.ptr.read_unaligned() };
+        self.done = aux_var_ser.key() == AuxVarType::Null;
+        self.ptr = unsafe { self.ptr.add(1) };
+        Some(aux_var_ser)
+    }
+}
+
+#[cfg(feature = "trusted-input")]
+impl<'a> FusedIterator for AuxVarSerializedUncheckedIter<'a> {}
+
+/// Iterator over all serialized entries in the auxiliary vector.
+/// This is a high-level version of [`AuxVarSerializedIter`] but unsafe,
+/// if the pointers are not valid in the address space of the caller.
+///
+/// Entries with a C-string, that is not valid UTF-8, are skipped.
+#[derive(Debug)]
+pub struct AuxVarIter<'a> {
+    serialized_iter: AuxVarSerializedIter<'a>,
+}
+
+impl<'a> AuxVarIter<'a> {
+    const fn new(serialized_iter: AuxVarSerializedIter<'a>) -> Self {
+        Self { serialized_iter }
     }
 }
 
@@ -265,14 +1415,14 @@ impl<'a> Iterator for AuxVarIter<'a> {
     type Item = AuxVar<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        unsafe {
-            self.serialized_iter
-                .next()
-                .map(|ref x| AuxVar::from_serialized(x))
-        }
+        self.serialized_iter
+            .by_ref()
+            .find_map(|ref x| unsafe { AuxVar::from_serialized(x) }.ok())
     }
 }
 
+impl<'a> FusedIterator for AuxVarIter<'a> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -349,4 +1499,1253 @@ mod tests {
         // debug already resolves memory addresses => in this test => memory errors
         // dbg!(parsed.aux_iter().collect::<Vec<_>>());
     }
+
+    #[test]
+    fn test_check_monotonic_data_pointers() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_arg_v("second_arg")
+            .add_env_v("ENV1=FOO")
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::Random([0; 16]));
+        let mut buf = vec![0; builder.total_size()];
+        unsafe {
+            let user_ptr = buf.as_ptr() as u64;
+            builder.serialize_into_buf(buf.as_mut_slice(), user_ptr);
+        }
+
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(parsed.check_monotonic_data_pointers(), Ok(()));
+
+        // swap argv[0] and argv[1]
+        let argv = buf[8..24].to_vec();
+        buf[8..16].copy_from_slice(&argv[8..16]);
+        buf[16..24].copy_from_slice(&argv[0..8]);
+
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert!(matches!(
+            parsed.check_monotonic_data_pointers(),
+            Err(LayoutError::NonMonotonicDataPointer { .. })
+        ));
+    }
+
+    #[test]
+    fn test_actual_len() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("ENV1=FOO")
+            .add_aux_v(AuxVar::Random([0; 16]))
+            .add_aux_v(AuxVar::ExecFn("./executable"));
+        let mut buf = vec![0; builder.total_size() + 1000];
+        unsafe {
+            let user_ptr = buf.as_ptr() as u64;
+            builder.serialize_into_buf(buf.as_mut_slice(), user_ptr);
+        }
+
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        // everything except for the final null
+        assert_eq!(unsafe { parsed.actual_len() }, builder.total_size() - 8);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_actual_len_linux() {
+        let buf = crate::test_data::test_data_x86_64_relocated();
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let actual_len = unsafe { parsed.actual_len() };
+        assert!(actual_len < buf.len());
+        // end of the "./cap\0" file name; followed by the final null
+        assert_eq!(actual_len, buf.len() - 8);
+    }
+
+    #[test]
+    fn test_program_name() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("prog")
+            .add_arg_v("x");
+        let mut buf = vec![0; builder.total_size()];
+        unsafe {
+            let user_ptr = buf.as_ptr() as u64;
+            builder.serialize_into_buf(buf.as_mut_slice(), user_ptr);
+        }
+
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(unsafe { parsed.program_name() }, Some("prog\0"));
+        assert_eq!(
+            unsafe { parsed.program_name() },
+            unsafe { parsed.argv_iter() }.next()
+        );
+
+        // the buffer ends inside of the first pointer of the argv-array
+        let truncated = InitialLinuxLibcStackLayout::from(&buf[..12]);
+        assert_eq!(unsafe { truncated.program_name() }, None);
+
+        let builder = InitialLinuxLibcStackLayoutBuilder::new();
+        let mut buf = vec![0; builder.total_size()];
+        unsafe {
+            let user_ptr = buf.as_ptr() as u64;
+            builder.serialize_into_buf(buf.as_mut_slice(), user_ptr);
+        }
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(unsafe { parsed.program_name() }, None);
+    }
+
+    #[test]
+    fn test_aux_serialized_vec() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::Uid(0xdeadbeef));
+        let mut buf = vec![0; builder.total_size()];
+        unsafe {
+            builder.serialize_into_buf(buf.as_mut_slice(), 0x1000);
+        }
+
+        let aux_vec = InitialLinuxLibcStackLayout::from(buf.as_slice()).aux_serialized_vec();
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(aux_vec.len(), parsed.aux_serialized_iter().count());
+        assert_eq!(aux_vec.len(), 4);
+        assert_eq!(aux_vec.last().unwrap().key(), AuxVarType::Null);
+    }
+
+    #[test]
+    fn test_check_argc_consistency() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_arg_v("second_arg")
+            .add_arg_v("third_arg")
+            .add_env_v("ENV1=FOO");
+        let mut buf = vec![0; builder.total_size()];
+        unsafe {
+            builder.serialize_into_buf(buf.as_mut_slice(), 0x1000);
+        }
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(parsed.check_argc_consistency(), Ok(()));
+
+        // lying argc
+        buf[0..size_of::<usize>()].copy_from_slice(&1000_usize.to_ne_bytes());
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(
+            parsed.check_argc_consistency(),
+            Err(LayoutError::ArgcMismatch {
+                argc: 1000,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_auxv_terminator() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new().add_aux_v(AuxVar::Uid(1000));
+        let mut buf = vec![0; builder.total_size()];
+        unsafe {
+            builder.serialize_into_buf(buf.as_mut_slice(), 0x1000);
+        }
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(parsed.check_auxv_terminator(), Ok(()));
+
+        // (AT_NULL, 0x1234)
+        let null_val_offset = parsed.get_auxv_ptr() as usize - buf.as_ptr() as usize
+            + 3 * size_of::<AuxVarSerialized>()
+            - size_of::<usize>();
+        buf[null_val_offset..null_val_offset + size_of::<usize>()]
+            .copy_from_slice(&0x1234_usize.to_ne_bytes());
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(
+            parsed.check_auxv_terminator(),
+            Err(LayoutError::NonZeroAtNull(0x1234))
+        );
+
+        // buffers that end inside the argv- or envv-array, or whose argc points far beyond
+        let buf = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_arg_v("second_arg")
+            .add_env_v("FOO=BAR")
+            .build_ref(None);
+        for len in [0, 4, 8 + 8, 8 + 3 * 8, 8 + 4 * 8] {
+            let parsed = InitialLinuxLibcStackLayout::from(&buf[..len]);
+            assert_eq!(
+                parsed.check_auxv_terminator(),
+                Err(LayoutError::MissingAuxvTerminator),
+                "{}",
+                len
+            );
+        }
+        let mut buf = buf;
+        buf[..8].copy_from_slice(&u64::MAX.to_ne_bytes());
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(
+            parsed.check_auxv_terminator(),
+            Err(LayoutError::MissingAuxvTerminator)
+        );
+    }
+
+    #[test]
+    fn test_layout_flavor() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("ENV1=FOO")
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::Random([0; 16]));
+        let mut buf = vec![0; builder.total_size()];
+        unsafe {
+            builder.serialize_into_buf(buf.as_mut_slice(), 0x1000);
+        }
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(parsed.layout_flavor(), LayoutFlavor::CrateBuilt);
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            let parsed =
+                InitialLinuxLibcStackLayout::from(&crate::test_data::TEST_DATA_X86_64.0[..]);
+            assert_eq!(parsed.layout_flavor(), LayoutFlavor::KernelLike);
+        }
+    }
+
+    #[test]
+    fn test_vdso_base_and_sysinfo() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_aux_v(AuxVar::SysinfoEhdr(0xf7ff_d000 as *const u8));
+        let mut buf = vec![0; builder.total_size()];
+        unsafe {
+            builder.serialize_into_buf(buf.as_mut_slice(), 0x1000);
+        }
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(parsed.vdso_base(), Some(0xf7ff_d000 as *const u8));
+        assert_eq!(parsed.sysinfo(), None);
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            let parsed =
+                InitialLinuxLibcStackLayout::from(&crate::test_data::TEST_DATA_X86_64.0[..]);
+            assert_eq!(parsed.vdso_base(), Some(0x7fff_f7ff_d000 as *const u8));
+            // AT_SYSINFO only exists on x86 (32-bit)
+            assert_eq!(parsed.sysinfo(), None);
+        }
+    }
+
+    #[test]
+    fn test_zero_args() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_env_v("ENV1=FOO")
+            .add_env_v("ENV2=BAR");
+        let buf = builder.build_ref(None);
+        // argc = 0, followed by the null pointer that terminates argv
+        assert_eq!(&buf[..2 * size_of::<u64>()], &[0; 16]);
+
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(parsed.argc(), 0);
+        assert_eq!(unsafe { parsed.argv_iter() }.count(), 0);
+        assert!(parsed.argv_ptrs().is_empty());
+        assert_eq!(unsafe { parsed.program_name() }, None);
+        assert_eq!(parsed.envc(), 2);
+        let envv = unsafe { parsed.envv_iter() }.collect::<Vec<_>>();
+        assert_eq!(envv, ["ENV1=FOO\0", "ENV2=BAR\0"]);
+        assert!(parsed.check_argc_consistency().is_ok());
+    }
+
+    #[test]
+    fn test_tolerant_extra_null_word() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("ENV1=FOO")
+            .add_aux_v(AuxVar::Uid(1000))
+            .add_aux_v(AuxVar::Pagesz(4096));
+        let mut buf = builder.build_ref(Some(0x1000));
+        let auxv_offset = InitialLinuxLibcStackLayout::from(buf.as_slice()).get_auxv_ptr() as usize
+            - buf.as_ptr() as usize;
+        // insert an additional null word before the auxiliary vector
+        for _ in 0..size_of::<usize>() {
+            buf.insert(auxv_offset, 0);
+        }
+
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(
+            parsed.check_auxv_terminator(),
+            Err(LayoutError::NonZeroAtNull(AuxVarType::Pagesz.val()))
+        );
+
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice()).tolerant(true);
+        assert_eq!(parsed.check_auxv_terminator(), Ok(()));
+        let keys = parsed
+            .aux_serialized_iter()
+            .map(|aux| (aux.key(), aux.val()))
+            .collect::<Vec<_>>();
+        assert!(keys.contains(&(AuxVarType::Uid, 1000)));
+        assert!(keys.contains(&(AuxVarType::Pagesz, 4096)));
+        assert_eq!(keys.last(), Some(&(AuxVarType::Null, 0)));
+
+        // valid layouts are not affected
+        let buf = builder.build_ref(Some(0x1000));
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice()).tolerant(true);
+        assert_eq!(parsed.aux_serialized_vec().len(), 4);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("ENV1=FOO")
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::Random([7; 16]))
+            .add_aux_v(AuxVar::Entry(0x401000 as *const u8))
+            .add_aux_v(AuxVar::Uid(1000));
+        let buf = builder.build_ref(None);
+        let snapshot = unsafe { InitialLinuxLibcStackLayout::from(buf.as_slice()).snapshot() };
+        drop(buf);
+
+        assert_eq!(snapshot.argc, 1);
+        assert_eq!(snapshot.argv, ["first_arg"]);
+        assert_eq!(snapshot.envv, ["ENV1=FOO"]);
+        let find = |key| {
+            snapshot
+                .auxv
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, val)| val.clone())
+        };
+        assert_eq!(
+            find(AuxVarType::Platform),
+            Some(SnapshotValue::String(String::from("x86_64")))
+        );
+        assert_eq!(
+            find(AuxVarType::Random),
+            Some(SnapshotValue::Bytes(vec![7; 16]))
+        );
+        assert_eq!(
+            find(AuxVarType::Entry),
+            Some(SnapshotValue::Pointer(0x401000))
+        );
+        assert_eq!(find(AuxVarType::Uid), Some(SnapshotValue::Integer(1000)));
+        assert_eq!(
+            find(AuxVarType::ExecFn),
+            Some(SnapshotValue::String(String::new()))
+        );
+        assert_eq!(
+            snapshot.auxv.last(),
+            Some(&(AuxVarType::Null, SnapshotValue::Integer(0)))
+        );
+    }
+
+    #[test]
+    fn test_detect_alignment_gaps() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("Foo")
+            .add_env_v("BAR=FOO")
+            .add_aux_v(AuxVar::Platform("x86_64"));
+        let padding = builder.alignment_padding();
+        assert_ne!(padding.before_aux_data, 0);
+        assert_ne!(padding.before_argv_data, 0);
+
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let auxv_end = 8 + 16 + 16 + 3 * size_of::<AuxVarSerialized>();
+        let aux_data = auxv_end + padding.before_aux_data;
+        assert_eq!(
+            parsed.detect_alignment_gaps(),
+            [
+                (auxv_end, padding.before_aux_data),
+                // + "x86_64\0"
+                (aux_data + 7, padding.before_argv_data)
+            ]
+        );
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            let buf = crate::test_data::test_data_x86_64_relocated();
+            let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+            let gaps = parsed.detect_alignment_gaps();
+            assert!(!gaps.is_empty());
+            assert!(gaps
+                .iter()
+                .all(|(offset, len)| buf[*offset..offset + len].iter().all(|b| *b == 0)));
+        }
+    }
+
+    #[test]
+    fn test_invalid_utf8_aux_string_does_not_panic() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::Uid(1000));
+        let mut buf = builder.build_ref(None);
+        let pos = buf.windows(6).position(|w| w == b"x86_64").unwrap();
+        buf[pos + 1] = 0xff;
+        let layout = InitialLinuxLibcStackLayout::from(buf.as_slice());
+
+        let auxv = unsafe { layout.aux_var_iter() }.collect::<Vec<_>>();
+        assert!(auxv.iter().all(|var| var.key() != AuxVarType::Platform));
+        assert!(auxv.iter().any(|var| var.eq_content(&AuxVar::Uid(1000))));
+        assert_eq!(
+            layout
+                .aux_var_iter_checked()
+                .find(|var| var.is_err())
+                .unwrap()
+                .unwrap_err(),
+            LayoutError::InvalidAuxData(AuxVarType::Platform)
+        );
+
+        let snapshot = unsafe { layout.snapshot() };
+        assert!(snapshot.auxv.contains(&(
+            AuxVarType::Platform,
+            SnapshotValue::String(String::from("x\u{fffd}6_64"))
+        )));
+        let _ = unsafe { layout.content_hash() };
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let builder = |entry: usize, uid: usize| {
+            InitialLinuxLibcStackLayoutBuilder::new()
+                .add_arg_v("first_arg")
+                .add_env_v("ENV1=FOO")
+                .add_aux_v(AuxVar::Platform("x86_64"))
+                .add_aux_v(AuxVar::Random([7; 16]))
+                .add_aux_v(AuxVar::Entry(entry as *const u8))
+                .add_aux_v(AuxVar::Uid(uid))
+        };
+        let hash = |builder: InitialLinuxLibcStackLayoutBuilder| {
+            // two different locations
+            let buf_1 = builder.build_ref(None);
+            let buf_2 = builder.build_ref(None);
+            assert_ne!(buf_1, buf_2);
+            let hash_1 =
+                unsafe { InitialLinuxLibcStackLayout::from(buf_1.as_slice()).content_hash() };
+            let hash_2 =
+                unsafe { InitialLinuxLibcStackLayout::from(buf_2.as_slice()).content_hash() };
+            assert_eq!(hash_1, hash_2);
+            hash_1
+        };
+
+        // absolute addresses are ignored
+        assert_eq!(hash(builder(0x401000, 1000)), hash(builder(0x555000, 1000)));
+        assert_ne!(hash(builder(0x401000, 1000)), hash(builder(0x401000, 0)));
+    }
+
+    #[test]
+    fn test_aux_var_iter_checked() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::Random([7; 16]))
+            .add_aux_v(AuxVar::Uid(1000));
+
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let aux = parsed
+            .aux_var_iter_checked()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(aux.len(), 5);
+        assert!(aux
+            .iter()
+            .any(|var| var.eq_content(&AuxVar::Platform("x86_64"))));
+        assert!(aux
+            .iter()
+            .any(|var| var.eq_content(&AuxVar::Random([7; 16]))));
+        assert!(aux.iter().any(|var| var.eq_content(&AuxVar::ExecFn(""))));
+
+        let buf = builder.build_ref(Some(0x1000));
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        for aux in parsed.aux_var_iter_checked() {
+            match aux {
+                Ok(var) => assert!(!var.key().value_in_data_area()),
+                Err(LayoutError::PointerOutOfRange(key)) => assert!(key.value_in_data_area()),
+                Err(e) => panic!("unexpected error: {:?}", e),
+            }
+        }
+        assert_eq!(
+            parsed.aux_var_iter_checked().filter(Result::is_err).count(),
+            3
+        );
+
+        // malformed entries are reported instead of panicking or reading beyond the buffer
+        let mut buf = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_aux_v(AuxVar::Uid(1000))
+            .build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let (null_offset, _) = parsed.auxv_offsets_iter().last().unwrap();
+        let last = |bytes: &[u8]| {
+            InitialLinuxLibcStackLayout::from(bytes)
+                .aux_var_iter_checked()
+                .last()
+                .unwrap()
+                .map(|var| var.key())
+        };
+        assert_eq!(
+            last(&buf[..null_offset]),
+            Err(LayoutError::MissingAuxvTerminator)
+        );
+        assert_eq!(last(&buf[..8 + 4]), Err(LayoutError::Truncated));
+        buf[24..32].copy_from_slice(&999_usize.to_ne_bytes());
+        assert!(InitialLinuxLibcStackLayout::from(buf.as_slice())
+            .aux_var_iter_checked()
+            .any(|aux| aux.err() == Some(LayoutError::UnknownAuxVarType(999))));
+        buf[null_offset + 8..null_offset + 16].copy_from_slice(&0x1234_usize.to_ne_bytes());
+        assert_eq!(last(&buf), Err(LayoutError::NonZeroAtNull(0x1234)));
+    }
+
+    #[test]
+    fn test_env_keys() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("PATH=/bin")
+            .add_env_v("HOME=/root");
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let keys = unsafe { parsed.env_keys() }.collect::<Vec<_>>();
+        assert_eq!(keys, ["PATH", "HOME"]);
+    }
+
+    #[test]
+    fn test_auxv_terminator() {
+        let buf = InitialLinuxLibcStackLayoutBuilder::new().build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let terminator = parsed.auxv_terminator().unwrap();
+        assert_eq!(terminator.key(), AuxVarType::Null);
+        assert_eq!(terminator.val(), 0);
+
+        // cut off the terminator
+        let parsed = InitialLinuxLibcStackLayout::from(&buf[..8 + 16 + 16]);
+        assert!(parsed.auxv_terminator().is_none());
+        // cut off inside the envv-array
+        let parsed = InitialLinuxLibcStackLayout::from(&buf[..8 + 8]);
+        assert!(parsed.auxv_terminator().is_none());
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            let parsed =
+                InitialLinuxLibcStackLayout::from(&crate::test_data::TEST_DATA_X86_64.0[..]);
+            let terminator = parsed.auxv_terminator().unwrap();
+            assert_eq!(terminator.key(), AuxVarType::Null);
+            assert_eq!(terminator.val(), 0);
+        }
+    }
+
+    #[test]
+    fn test_framed() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("ENV1=FOO")
+            .add_aux_v(AuxVar::Platform("x86_64"));
+        let buf = builder.build_framed(None);
+        assert_eq!(buf.len(), 8 + builder.total_size());
+
+        let parsed = InitialLinuxLibcStackLayout::from_framed(&buf).unwrap();
+        assert_eq!(parsed.argc(), 1);
+        assert_eq!(unsafe { parsed.argv_iter() }.next(), Some("first_arg\0"));
+        assert!(parsed
+            .aux_var_iter_checked()
+            .any(|aux| aux.unwrap().eq_content(&AuxVar::Platform("x86_64"))));
+
+        assert_eq!(
+            InitialLinuxLibcStackLayout::from_framed(&buf[..buf.len() - 1]).unwrap_err(),
+            LayoutError::FrameLengthMismatch {
+                header: builder.total_size() as u64,
+                actual: builder.total_size() - 1
+            }
+        );
+        assert!(InitialLinuxLibcStackLayout::from_framed(&buf[..4]).is_err());
+    }
+
+    #[test]
+    fn test_unknown_auxv_types() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new().add_aux_v(AuxVar::Uid(1000));
+        let mut buf = builder.build_ref(Some(0x1000));
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert!(parsed.unknown_auxv_types().is_empty());
+
+        // replace the key of AT_UID (first entry) with an unknown key
+        let auxv_offset = 8 + 8 + 8;
+        assert_eq!(
+            usize::from_ne_bytes(
+                buf[auxv_offset..auxv_offset + size_of::<usize>()]
+                    .try_into()
+                    .unwrap()
+            ),
+            AuxVarType::Uid.val()
+        );
+        buf[auxv_offset..auxv_offset + size_of::<usize>()]
+            .copy_from_slice(&999_usize.to_ne_bytes());
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(parsed.unknown_auxv_types(), [999]);
+
+        // an argc that points beyond the buffer doesn't underflow the offset computation
+        for argc in [usize::MAX, usize::MAX / 8, buf.len()] {
+            buf[..8].copy_from_slice(&(argc as u64).to_ne_bytes());
+            let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+            assert!(parsed.unknown_auxv_types().is_empty(), "{}", argc);
+        }
+        // too short for argc
+        let parsed = InitialLinuxLibcStackLayout::from(&buf[..4]);
+        assert!(parsed.unknown_auxv_types().is_empty());
+    }
+
+    #[test]
+    fn test_from_raw_parts() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_arg_v("second_arg");
+        let buf = builder.build_ref(None);
+        let parsed = unsafe {
+            InitialLinuxLibcStackLayout::from_raw_parts(buf.as_ptr(), buf.len(), Some(2))
+        };
+        assert_eq!(
+            unsafe { parsed.argv_iter() }.collect::<Vec<_>>(),
+            ["first_arg\0", "second_arg\0"]
+        );
+        let parsed =
+            unsafe { InitialLinuxLibcStackLayout::from_raw_parts(buf.as_ptr(), buf.len(), None) };
+        assert_eq!(parsed.argc(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_raw_parts_argc_mismatch() {
+        let buf = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .build_ref(None);
+        let _ = unsafe {
+            InitialLinuxLibcStackLayout::from_raw_parts(buf.as_ptr(), buf.len(), Some(2))
+        };
+    }
+
+    #[test]
+    fn test_preserves_argv0() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new().add_arg_v("first_arg");
+        let buf = builder.build_ref(Some(0x1000));
+        assert!(!InitialLinuxLibcStackLayout::from(buf.as_slice()).preserves_argv0());
+
+        let builder = builder.add_aux_v(AuxVar::Flags(AuxVarFlags::PRESERVE_ARGV0));
+        let buf = builder.build_ref(Some(0x1000));
+        assert!(InitialLinuxLibcStackLayout::from(buf.as_slice()).preserves_argv0());
+    }
+
+    #[test]
+    fn test_envv_offset() {
+        #[cfg(target_arch = "x86_64")]
+        {
+            let parsed =
+                InitialLinuxLibcStackLayout::from(&crate::test_data::TEST_DATA_X86_64.0[..]);
+            assert_eq!(parsed.envv_offset(), 8 + (parsed.argc() + 1) * 8);
+        }
+    }
+
+    #[test]
+    fn test_argv_envv_total_bytes() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_arg_v("second_arg\0")
+            .add_env_v("FOO=BAR")
+            .add_env_v("HOME=/root")
+            .add_env_v("SHELL=/bin/sh");
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        unsafe {
+            assert_eq!(parsed.argv_total_bytes(), builder.argv_data_area_size());
+            assert_eq!(parsed.argv_total_bytes(), 10 + 11);
+            assert_eq!(parsed.envv_total_bytes(), builder.envv_data_area_size());
+            assert_eq!(parsed.envv_total_bytes(), 8 + 11 + 14);
+        }
+    }
+
+    #[test]
+    fn test_reemit() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("FOO=BAR")
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::Uid(1000));
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let old_base = buf.as_ptr() as usize;
+        let new_base = 0x7fff_0000;
+        let reemitted = unsafe { parsed.reemit(new_base) };
+        assert_eq!(reemitted.len(), unsafe { parsed.actual_len() });
+
+        // rebased to new_base, everything else is byte-exact
+        let rebased = InitialLinuxLibcStackLayout::from(reemitted.as_slice());
+        let rebase = |ptrs: &[usize]| {
+            ptrs.iter()
+                .map(|ptr| ptr - old_base + new_base)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rebased.argv_ptrs(), rebase(parsed.argv_ptrs()));
+        assert_eq!(rebased.envv_ptrs(), rebase(parsed.envv_ptrs()));
+        let platform = |layout: &InitialLinuxLibcStackLayout| {
+            layout
+                .aux_serialized_iter()
+                .find(|x| x.key() == AuxVarType::Platform)
+                .unwrap()
+                .val()
+        };
+        assert_eq!(platform(&rebased), platform(&parsed) - old_base + new_base);
+        let data_offset = parsed.argv_ptrs()[0] - old_base;
+        assert_eq!(reemitted[data_offset..], buf[data_offset..reemitted.len()]);
+
+        // the order of a real kernel capture is preserved, whereas the builder sorts the entries
+        #[cfg(target_arch = "x86_64")]
+        {
+            let parsed =
+                InitialLinuxLibcStackLayout::from(&crate::test_data::TEST_DATA_X86_64.0[..]);
+            let keys = |layout: &InitialLinuxLibcStackLayout| {
+                layout
+                    .aux_serialized_iter()
+                    .map(|x| x.key())
+                    .collect::<Vec<_>>()
+            };
+            let reemitted = unsafe { parsed.reemit(0x1000) };
+            let original_keys = keys(&parsed);
+            let mut sorted_keys = original_keys.clone();
+            sorted_keys.sort();
+            assert_ne!(original_keys, sorted_keys);
+            assert_eq!(
+                keys(&InitialLinuxLibcStackLayout::from(reemitted.as_slice())),
+                original_keys
+            );
+        }
+    }
+
+    #[test]
+    fn test_estimate_stack_gap() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("FOO=BAR")
+            .add_aux_v(AuxVar::Platform("x86_64"));
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let data_end = buf.as_ptr() as usize + unsafe { parsed.actual_len() };
+        assert_eq!(parsed.estimate_stack_gap(data_end + 0x1234), Some(0x1234));
+        assert_eq!(parsed.estimate_stack_gap(data_end), Some(0));
+        assert_eq!(parsed.estimate_stack_gap(data_end - 1), None);
+    }
+
+    #[test]
+    fn test_structurally_eq() {
+        let builder = || {
+            InitialLinuxLibcStackLayoutBuilder::new()
+                .add_arg_v("first_arg")
+                .add_env_v("FOO=BAR")
+                .add_aux_v(AuxVar::Platform("x86_64"))
+                .add_aux_v(AuxVar::Random([1; 16]))
+                .add_aux_v(AuxVar::Uid(1000))
+        };
+        let buf = builder()
+            .add_aux_v(AuxVar::Entry(0x1000 as *const u8))
+            .build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+
+        // another copy at a different address with other pointer values
+        let other_buf = builder()
+            .add_aux_v(AuxVar::Entry(0x2000 as *const u8))
+            .add_aux_v(AuxVar::Random([2; 16]))
+            .build_ref(None);
+        assert_ne!(buf.as_ptr(), other_buf.as_ptr());
+        let other = InitialLinuxLibcStackLayout::from(other_buf.as_slice());
+        assert!(unsafe { parsed.structurally_eq(&other) });
+
+        let other_buf = builder()
+            .add_aux_v(AuxVar::Entry(0x1000 as *const u8))
+            .add_env_v("FOO=BAZ")
+            .build_ref(None);
+        let other = InitialLinuxLibcStackLayout::from(other_buf.as_slice());
+        assert!(!unsafe { parsed.structurally_eq(&other) });
+
+        let other_buf = builder()
+            .add_aux_v(AuxVar::Entry(0x1000 as *const u8))
+            .add_aux_v(AuxVar::Uid(0))
+            .build_ref(None);
+        let other = InitialLinuxLibcStackLayout::from(other_buf.as_slice());
+        assert!(!unsafe { parsed.structurally_eq(&other) });
+    }
+
+    #[test]
+    fn test_auxv_offsets_iter() {
+        #[cfg(target_arch = "x86_64")]
+        {
+            let parsed =
+                InitialLinuxLibcStackLayout::from(&crate::test_data::TEST_DATA_X86_64.0[..]);
+            let offsets = parsed.auxv_offsets_iter().collect::<Vec<_>>();
+            assert_eq!(offsets.len(), parsed.aux_serialized_iter().count());
+            assert_eq!(
+                offsets[0].0,
+                parsed.envv_offset() + (parsed.envc() + 1) * size_of::<usize>()
+            );
+            for pair in offsets.windows(2) {
+                assert_eq!(pair[1].0 - pair[0].0, size_of::<AuxVarSerialized>());
+            }
+            for ((offset, aux), expected) in offsets.iter().zip(parsed.aux_serialized_iter()) {
+                assert_eq!(aux.key(), expected.key());
+                let bytes = &crate::test_data::TEST_DATA_X86_64.0[*offset..];
+                assert_eq!(
+                    usize::from_ne_bytes(bytes[..8].try_into().unwrap()),
+                    expected.key().val()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_collect_argv_into() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_arg_v("second_arg");
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+
+        let (argv, count) = unsafe { parsed.collect_argv_into::<4>() }.unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(
+            argv,
+            [Some("first_arg\0"), Some("second_arg\0"), None, None]
+        );
+
+        let err = unsafe { parsed.collect_argv_into::<1>() }.unwrap_err();
+        assert_eq!(
+            err,
+            LayoutError::CapacityExceeded {
+                required: 2,
+                capacity: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_auxv_table() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_aux_v(AuxVar::Pagesz(4096))
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::Secure(false));
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let table = unsafe { parsed.auxv_table() };
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1 + parsed.aux_serialized_iter().count());
+        assert!(lines[0].starts_with("TYPE"));
+        assert!(lines.contains(&"AT_PAGESZ               6  4096"));
+        assert!(lines.contains(&"AT_PLATFORM            15  \"x86_64\""));
+        assert!(lines.contains(&"AT_SECURE              23  false"));
+        assert!(lines.contains(&"AT_NULL                 0  -"));
+    }
+
+    #[test]
+    fn test_entries_only() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("FOO=BAR")
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::Pagesz(4096));
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(
+            parsed.argv_iter_checked().collect::<Vec<_>>(),
+            [Some("first_arg\0")]
+        );
+
+        // truncate right after the auxv terminator
+        let (auxv_end, _) = parsed.auxv_offsets_iter().last().unwrap();
+        let truncated = &buf[..auxv_end + size_of::<AuxVarSerialized>()];
+        let parsed = InitialLinuxLibcStackLayout::entries_only(truncated, 1).unwrap();
+        assert_eq!(parsed.argv_iter_checked().collect::<Vec<_>>(), [None]);
+        assert_eq!(parsed.envv_iter_checked().collect::<Vec<_>>(), [None]);
+        let auxv = parsed.aux_var_iter_checked().collect::<Vec<_>>();
+        assert!(auxv
+            .iter()
+            .any(|aux| matches!(aux, Ok(AuxVar::Pagesz(4096)))));
+        assert!(auxv
+            .iter()
+            .any(|aux| *aux == Err(LayoutError::PointerOutOfRange(AuxVarType::Platform))));
+
+        assert_eq!(
+            InitialLinuxLibcStackLayout::entries_only(truncated, 2).unwrap_err(),
+            LayoutError::ArgcUnexpected {
+                stored: 1,
+                expected: 2
+            }
+        );
+        assert_eq!(
+            InitialLinuxLibcStackLayout::entries_only(&truncated[..4], 1).unwrap_err(),
+            LayoutError::Truncated
+        );
+        assert_eq!(
+            InitialLinuxLibcStackLayout::entries_only(&truncated[..truncated.len() - 16], 1)
+                .unwrap_err(),
+            LayoutError::MissingAuxvTerminator
+        );
+    }
+
+    #[test]
+    fn test_peek_argc() {
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(peek_argc(&crate::test_data::TEST_DATA_X86_64.0[..]), Ok(4));
+        let buf = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .build_ref(None);
+        assert_eq!(peek_argc(&buf), Ok(1));
+        assert_eq!(peek_argc(&buf[..2]), Err(LayoutError::Truncated));
+    }
+
+    #[test]
+    fn test_arg_bytes() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("prog")
+            .add_arg_v("--foo");
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        unsafe {
+            assert_eq!(parsed.arg_bytes(0), Some(&b"prog\0"[..]));
+            assert_eq!(parsed.arg_bytes(1), Some(&b"--foo\0"[..]));
+            assert_eq!(parsed.arg_bytes(2), None);
+        }
+    }
+
+    #[test]
+    fn test_env_entries() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_env_v("PATH=/bin")
+            .add_env_v("FOO=BAR=BAZ")
+            .add_env_v("EMPTY");
+        let buf = builder.build_ref(None);
+        let entries = unsafe { InitialLinuxLibcStackLayout::from(buf.as_slice()).env_entries() };
+        drop(buf);
+        let entry = |key: &str, value: &str| EnvEntry {
+            key: String::from(key),
+            value: String::from(value),
+        };
+        assert_eq!(
+            entries,
+            [
+                entry("PATH", "/bin"),
+                entry("FOO", "BAR=BAZ"),
+                entry("EMPTY", "")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_iterators_fused() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("FOO=BAR")
+            .add_aux_v(AuxVar::Platform("x86_64"));
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+
+        fn assert_fused<I: FusedIterator>(mut iter: I, count: usize) {
+            assert_eq!(iter.by_ref().count(), count);
+            for _ in 0..3 {
+                assert!(iter.next().is_none());
+            }
+        }
+        assert_fused(parsed.argv_ptr_iter(), 1);
+        assert_fused(parsed.envv_ptr_iter(), 1);
+        assert_fused(parsed.aux_serialized_iter(), 3);
+        unsafe {
+            assert_fused(parsed.argv_iter(), 1);
+            assert_fused(parsed.envv_iter(), 1);
+            assert_fused(parsed.aux_var_iter(), 3);
+            #[cfg(feature = "trusted-input")]
+            assert_fused(parsed.aux_serialized_iter_unchecked(), 3);
+        }
+    }
+
+    #[test]
+    fn test_random_address() {
+        #[cfg(target_arch = "x86_64")]
+        {
+            let parsed =
+                InitialLinuxLibcStackLayout::from(&crate::test_data::TEST_DATA_X86_64.0[..]);
+            let raw = parsed
+                .aux_serialized_iter()
+                .find(|aux| aux.key() == AuxVarType::Random)
+                .unwrap()
+                .val();
+            assert_eq!(parsed.random_address(), Some(raw));
+            assert_ne!(raw, 0);
+        }
+        let buf = InitialLinuxLibcStackLayoutBuilder::new().build_ref(None);
+        assert_eq!(
+            InitialLinuxLibcStackLayout::from(buf.as_slice()).random_address(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_relative_view() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_arg_v("second_arg")
+            .add_env_v("FOO=BAR")
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::Entry(0x1000 as *const u8));
+        let buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let view = parsed.relative_view(buf.as_ptr() as usize);
+
+        let len = buf.len() as isize;
+        assert!(view.argv.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(view
+            .argv
+            .iter()
+            .chain(&view.envv)
+            .all(|x| (0..len).contains(x)));
+        assert!(view.argv.last() < view.envv.first());
+        let value = |key| view.auxv.iter().find(|(typ, _)| *typ == key).unwrap().1;
+        assert!(matches!(
+            value(AuxVarType::Platform),
+            RelativeValue::Offset(offset) if (0..len).contains(&offset)
+        ));
+        assert_eq!(value(AuxVarType::Entry), RelativeValue::Immediate(0x1000));
+        assert_eq!(view.auxv.len(), parsed.aux_serialized_iter().count());
+
+        // independent of the location of the data structure
+        let other_buf = builder.build_ref(None);
+        let other = InitialLinuxLibcStackLayout::from(other_buf.as_slice());
+        assert_eq!(other.relative_view(other_buf.as_ptr() as usize), view);
+    }
+
+    #[test]
+    fn test_align_offset_for() {
+        let align = required_alignment();
+        assert_eq!(align, core::mem::align_of::<usize>());
+
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("FOO=BAR");
+        let mut storage = vec![0_u8; builder.total_size() + 2 * align];
+        let base = storage.as_ptr().align_offset(align) + align;
+        let layout = builder.build_ref(Some(storage[base..].as_ptr() as u64));
+        storage[base..base + layout.len()].copy_from_slice(&layout);
+
+        // deliberately offset by 3 bytes
+        let bytes = &storage[base - 3..];
+        assert_eq!(peek_argc(bytes), Err(LayoutError::UnalignedBuffer));
+        let skip = align_offset_for(bytes);
+        assert_eq!(skip, 3);
+        let aligned = &bytes[skip..];
+        assert_eq!(align_offset_for(aligned), 0);
+        assert_eq!(peek_argc(aligned), Ok(1));
+        let parsed = InitialLinuxLibcStackLayout::from(aligned);
+        unsafe {
+            assert_eq!(parsed.argv_iter().collect::<Vec<_>>(), ["first_arg\0"]);
+            assert_eq!(parsed.envv_iter().collect::<Vec<_>>(), ["FOO=BAR\0"]);
+        }
+    }
+
+    #[test]
+    fn test_platform_execfn_checked() {
+        let buf = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("./executable")
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::ExecFn("./executable"))
+            .build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(parsed.platform_checked(), Some("x86_64"));
+        assert_eq!(parsed.execfn_checked(), Some("./executable"));
+
+        // data structure for another address space
+        let buf = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .build_ref(Some(0x7fff_0000));
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(parsed.platform_checked(), None);
+        assert_eq!(parsed.execfn_checked(), None);
+    }
+
+    #[test]
+    fn test_has_terminator() {
+        #[cfg(target_arch = "x86_64")]
+        {
+            let bytes = &crate::test_data::TEST_DATA_X86_64.0[..];
+            assert!(InitialLinuxLibcStackLayout::from(bytes).has_terminator());
+            // truncated in the middle of the auxiliary vector
+            assert!(!InitialLinuxLibcStackLayout::from(&bytes[..200]).has_terminator());
+        }
+
+        let buf = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_aux_v(AuxVar::Pagesz(4096))
+            .build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert!(parsed.has_terminator());
+        let (null_offset, _) = parsed.auxv_offsets_iter().last().unwrap();
+        let truncated = InitialLinuxLibcStackLayout::from(&buf[..null_offset]);
+        assert!(!truncated.has_terminator());
+        // truncated inside the argv-array
+        let truncated = InitialLinuxLibcStackLayout::from(&buf[..8]);
+        assert!(!truncated.has_terminator());
+    }
+
+    #[test]
+    fn test_try_raw_iters() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_arg_v("second_arg")
+            .add_env_v("FOO=BAR")
+            .add_aux_v(AuxVar::Platform("x86_64"))
+            .add_aux_v(AuxVar::Pagesz(4096));
+        let mut buf = builder.build_ref(None);
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert_eq!(
+            parsed.try_argv_raw_iter().collect::<Vec<_>>(),
+            parsed.argv_ptr_iter().map(Ok).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            parsed.try_envv_raw_iter().collect::<Vec<_>>(),
+            parsed.envv_ptr_iter().map(Ok).collect::<Vec<_>>()
+        );
+        let auxv = parsed
+            .try_auxv_raw_iter()
+            .map(|aux| aux.map(|aux| (aux.key(), aux.val())))
+            .collect::<Vec<_>>();
+        let expected = parsed
+            .aux_serialized_iter()
+            .map(|aux| Ok((aux.key(), aux.val())))
+            .collect::<Vec<_>>();
+        assert_eq!(auxv, expected);
+
+        // every truncation is reported instead of panicking
+        let (null_offset, _) = parsed.auxv_offsets_iter().last().unwrap();
+        let (auxv_offset, _) = parsed.auxv_offsets_iter().next().unwrap();
+        for len in 0..null_offset + 2 * size_of::<usize>() {
+            let truncated = InitialLinuxLibcStackLayout::from(&buf[..len]);
+            let args = truncated.try_argv_raw_iter().collect::<Vec<_>>();
+            let envs = truncated.try_envv_raw_iter().collect::<Vec<_>>();
+            let auxv = truncated.try_auxv_raw_iter().collect::<Vec<_>>();
+            assert!(args.len() <= 3 && envs.len() <= 2);
+            let expected = if len < auxv_offset {
+                LayoutError::Truncated
+            } else {
+                LayoutError::MissingAuxvTerminator
+            };
+            assert_eq!(auxv.last().unwrap().as_ref().unwrap_err(), &expected);
+        }
+        assert_eq!(
+            InitialLinuxLibcStackLayout::from(&buf[..4])
+                .try_argv_raw_iter()
+                .collect::<Vec<_>>(),
+            [Err(LayoutError::Truncated)]
+        );
+
+        // unknown keys are reported and skipped
+        let (offset, _) = parsed.auxv_offsets_iter().next().unwrap();
+        buf[offset..offset + size_of::<usize>()].copy_from_slice(&999_usize.to_ne_bytes());
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        let auxv = parsed.try_auxv_raw_iter().collect::<Vec<_>>();
+        assert!(matches!(auxv[0], Err(LayoutError::UnknownAuxVarType(999))));
+        assert_eq!(auxv.len(), expected.len());
+        assert!(matches!(auxv.last(), Some(Ok(aux)) if aux.key() == AuxVarType::Null));
+
+        // pointers outside of the buffer are reported
+        let buf = builder.build_ref(Some(0x1000));
+        let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+        assert!(parsed
+            .try_argv_raw_iter()
+            .chain(parsed.try_envv_raw_iter())
+            .all(
+                |ptr| matches!(ptr, Err(LayoutError::DataPointerOutOfRange(ptr)) if ptr >= 0x1000)
+            ));
+        let auxv = parsed.try_auxv_raw_iter().collect::<Vec<_>>();
+        assert!(auxv.iter().any(|aux| matches!(
+            aux,
+            Err(LayoutError::PointerOutOfRange(AuxVarType::Platform))
+        )));
+        assert!(auxv
+            .iter()
+            .any(|aux| matches!(aux, Ok(aux) if aux.key() == AuxVarType::Pagesz)));
+    }
+
+    #[test]
+    fn test_try_new() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_env_v("FOO=BAR");
+        let mut storage = vec![0_u8; builder.total_size() + 2 * size_of::<usize>()];
+        let base = storage.as_ptr().align_offset(size_of::<usize>()) + size_of::<usize>();
+        let layout = builder.build_ref(Some(storage[base..].as_ptr() as u64));
+        storage[base..base + layout.len()].copy_from_slice(&layout);
+
+        let parsed = InitialLinuxLibcStackLayout::try_new(&storage[base..], Some(1)).unwrap();
+        assert_eq!(parsed.argc(), 1);
+        assert!(InitialLinuxLibcStackLayout::try_new(&storage[base..], None).is_ok());
+        assert_eq!(
+            InitialLinuxLibcStackLayout::try_new(&storage[base - 1..], None).unwrap_err(),
+            LayoutError::UnalignedBuffer
+        );
+        assert_eq!(
+            InitialLinuxLibcStackLayout::try_new(&storage[base..], Some(2)).unwrap_err(),
+            LayoutError::ArgcUnexpected {
+                stored: 1,
+                expected: 2
+            }
+        );
+        assert_eq!(
+            InitialLinuxLibcStackLayout::try_new(&storage[base..base + 4], Some(1)).unwrap_err(),
+            LayoutError::Truncated
+        );
+    }
+
+    #[cfg(all(feature = "trusted-input", target_arch = "x86_64"))]
+    #[test]
+    fn test_aux_serialized_iter_unchecked() {
+        let parsed = InitialLinuxLibcStackLayout::from(&crate::test_data::TEST_DATA_X86_64.0[..]);
+        let expected = parsed.aux_serialized_vec();
+        let actual = unsafe { parsed.aux_serialized_iter_unchecked() }.collect::<Vec<_>>();
+        assert_eq!(expected.len(), actual.len());
+        for (expected, actual) in expected.iter().zip(actual.iter()) {
+            assert_eq!(expected.key(), actual.key());
+            assert_eq!(expected.val(), actual.val());
+        }
+    }
+
+    #[test]
+    fn test_argv_envv_ptrs() {
+        let builder = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .add_arg_v("second_arg")
+            .add_env_v("ENV1=FOO");
+        let mut buf = vec![0_u64; builder.total_size() / size_of::<u64>() + 1];
+        let buf = unsafe {
+            core::slice::from_raw_parts_mut(buf.as_mut_ptr().cast::<u8>(), builder.total_size())
+        };
+        unsafe {
+            builder.serialize_into_buf(buf, 0x1000);
+        }
+        let parsed = InitialLinuxLibcStackLayout::from(&buf[..]);
+        assert_eq!(parsed.argv_ptrs().len(), parsed.argc());
+        assert_eq!(parsed.envv_ptrs().len(), parsed.envc());
+        assert!(parsed
+            .argv_ptrs()
+            .iter()
+            .chain(parsed.envv_ptrs())
+            .zip(parsed.argv_ptr_iter().chain(parsed.envv_ptr_iter()))
+            .all(|(a, b)| *a == b as usize));
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            let parsed =
+                InitialLinuxLibcStackLayout::from(&crate::test_data::TEST_DATA_X86_64.0[..]);
+            assert_eq!(parsed.argv_ptrs().len(), parsed.argc());
+            assert_eq!(parsed.argv_ptrs()[0], 0x7fff_ffff_efa7);
+            assert_eq!(parsed.envv_ptrs().len(), 3);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "pointer array exceeds the buffer")]
+    fn test_argv_ptrs_huge_argc() {
+        let mut buf = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_arg_v("first_arg")
+            .build_ref(None);
+        // the size of the array overflows
+        buf[..8].copy_from_slice(&(1_u64 << 61).to_ne_bytes());
+        let _ = InitialLinuxLibcStackLayout::from(buf.as_slice()).argv_ptrs();
+    }
+
+    #[test]
+    #[should_panic(expected = "pointer array exceeds the buffer")]
+    fn test_envv_ptrs_huge_argc() {
+        let mut buf = InitialLinuxLibcStackLayoutBuilder::new()
+            .add_env_v("FOO=BAR")
+            .build_ref(None);
+        buf[..8].copy_from_slice(&(1_u64 << 61).to_ne_bytes());
+        let _ = InitialLinuxLibcStackLayout::from(buf.as_slice()).envv_ptrs();
+    }
 }
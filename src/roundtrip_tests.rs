@@ -0,0 +1,267 @@
+/*
+MIT License
+
+Copyright (c) 2021 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Roundtrip tests: Randomized inputs are serialized with
+//! [`InitialLinuxLibcStackLayoutBuilder`] and parsed again with
+//! [`InitialLinuxLibcStackLayout`]. The parsed structure must be equal to the input.
+//! The pseudo random number generator uses a fixed seed so that failures are reproducible.
+
+use crate::{
+    AuxVar, AuxVarFlags, AuxVarType, InitialLinuxLibcStackLayout,
+    InitialLinuxLibcStackLayoutBuilder,
+};
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Simple xorshift64 generator. Good enough to produce diverse test inputs.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, max: usize) -> usize {
+        (self.next() % max as u64) as usize
+    }
+
+    fn string(&mut self, max_len: usize) -> String {
+        const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_-./";
+        (0..self.below(max_len + 1))
+            .map(|_| CHARS[self.below(CHARS.len())] as char)
+            .collect()
+    }
+}
+
+/// Randomly generated input for the builder.
+struct Input {
+    argv: Vec<String>,
+    envv: Vec<String>,
+    platform: String,
+    execfn: String,
+    aux_seeds: Vec<(usize, usize)>,
+}
+
+impl Input {
+    fn generate(rng: &mut Rng) -> Self {
+        let argv = (0..rng.below(6)).map(|_| rng.string(24)).collect();
+        let envv = (0..rng.below(6))
+            .map(|_| {
+                let mut var = rng.string(12);
+                var.push('=');
+                var.push_str(&rng.string(32));
+                var
+            })
+            .collect();
+        let platform = rng.string(10);
+        let execfn = rng.string(40);
+        let aux_seeds = (0..rng.below(12))
+            .map(|_| (rng.below(13), rng.next() as usize))
+            .collect();
+        Self {
+            argv,
+            envv,
+            platform,
+            execfn,
+            aux_seeds,
+        }
+    }
+
+    /// Maps the random seeds to aux entries. Entries with a key that is already present
+    /// replace the previous one, as in the builder.
+    fn aux_vars(&self) -> Vec<AuxVar<'_>> {
+        self.aux_seeds
+            .iter()
+            .map(|&(kind, val)| match kind {
+                0 => AuxVar::Pagesz(val),
+                1 => AuxVar::Uid(val),
+                2 => AuxVar::Gid(val),
+                3 => AuxVar::Clktck(val),
+                4 => AuxVar::HwCap(val),
+                5 => AuxVar::Secure(val % 2 == 0),
+                6 => AuxVar::Entry(val as _),
+                7 => AuxVar::Phdr(val as _),
+                8 => AuxVar::Flags(AuxVarFlags::empty()),
+                9 => AuxVar::Random((val as u128 * 0x9e37_79b9).to_ne_bytes()),
+                10 => AuxVar::Platform(&self.platform),
+                11 => AuxVar::ExecFn(&self.execfn),
+                _ => AuxVar::MinSigStkSz(val),
+            })
+            .collect()
+    }
+}
+
+/// Builds and parses the input and compares the result with the input.
+fn assert_roundtrip(input: &Input) {
+    let mut builder = InitialLinuxLibcStackLayoutBuilder::new();
+    let mut expected_aux = BTreeSet::new();
+    expected_aux.insert(AuxVar::ExecFn("\0"));
+    expected_aux.insert(AuxVar::Null);
+    for arg in &input.argv {
+        builder = builder.add_arg_v(arg);
+    }
+    for env in &input.envv {
+        builder = builder.add_env_v(env);
+    }
+    for aux in input.aux_vars() {
+        builder = builder.add_aux_v(aux);
+    }
+    // AuxVar is not Clone; generate the entries a second time
+    for aux in input.aux_vars() {
+        expected_aux.replace(aux);
+    }
+
+    let mut buf = vec![0; builder.total_size()];
+    unsafe {
+        // user_addr == write_addr => all pointers can be dereferenced
+        let user_ptr = buf.as_ptr();
+        builder.serialize_into_buf(&mut buf, user_ptr as u64);
+    }
+
+    let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+    assert_eq!(parsed.argc(), input.argv.len());
+    assert_eq!(parsed.envc(), input.envv.len());
+
+    let trim_null = |cstr: &str| String::from(cstr.strip_suffix('\0').unwrap_or(cstr));
+    let argv = unsafe { parsed.argv_iter() }
+        .map(trim_null)
+        .collect::<Vec<_>>();
+    let envv = unsafe { parsed.envv_iter() }
+        .map(trim_null)
+        .collect::<Vec<_>>();
+    assert_eq!(argv, input.argv);
+    assert_eq!(envv, input.envv);
+
+    let mut aux = unsafe { parsed.aux_var_iter() }.collect::<Vec<_>>();
+    aux.sort();
+    assert_eq!(aux.len(), expected_aux.len());
+    for (actual, expected) in aux.iter().zip(expected_aux.iter()) {
+        assert!(
+            actual.eq_content(expected),
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+}
+
+#[test]
+fn test_roundtrip_empty() {
+    let input = Input {
+        argv: Vec::new(),
+        envv: Vec::new(),
+        platform: String::new(),
+        execfn: String::new(),
+        aux_seeds: Vec::new(),
+    };
+    assert_roundtrip(&input);
+}
+
+#[test]
+fn test_roundtrip_randomized() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..64 {
+        assert_roundtrip(&Input::generate(&mut rng));
+    }
+}
+
+#[test]
+fn test_roundtrip_all_aux_kinds() {
+    let input = Input {
+        argv: vec![String::from("./prog"), String::from("--flag")],
+        envv: vec![String::from("HOME=/root"), String::from("EMPTY=")],
+        platform: String::from("x86_64"),
+        execfn: String::from("/usr/bin/prog"),
+        aux_seeds: (0..13).map(|kind| (kind, 0x1000 * (kind + 1))).collect(),
+    };
+    assert_roundtrip(&input);
+}
+
+#[test]
+fn test_roundtrip_replaced_aux_entries() {
+    let input = Input {
+        argv: vec![String::new()],
+        envv: Vec::new(),
+        platform: String::from("aarch64"),
+        execfn: String::from("a"),
+        aux_seeds: vec![(1, 1), (1, 2), (0, 4096), (0, 0x10000), (11, 0), (10, 0)],
+    };
+    assert_roundtrip(&input);
+}
+
+#[test]
+fn test_roundtrip_long_strings() {
+    let mut rng = Rng(0xdead_beef_cafe_babe);
+    let input = Input {
+        argv: (0..32).map(|_| rng.string(200)).collect(),
+        envv: (0..32)
+            .map(|i| format!("VAR{}={}", i, rng.string(300)))
+            .collect(),
+        platform: rng.string(64),
+        execfn: rng.string(255),
+        aux_seeds: (0..13).map(|kind| (kind, rng.next() as usize)).collect(),
+    };
+    assert_roundtrip(&input);
+}
+
+#[test]
+fn test_roundtrip_hwcap3_hwcap4() {
+    let buf = InitialLinuxLibcStackLayoutBuilder::new()
+        .add_aux_v(AuxVar::HwCap2(0x2))
+        .add_aux_v(AuxVar::HwCap3(0x1234))
+        .add_aux_v(AuxVar::HwCap4(0x5678))
+        .build_ref(None);
+    let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+    let aux = unsafe { parsed.aux_var_iter() }.collect::<Vec<_>>();
+    let find = |key| {
+        aux.iter()
+            .find(|x| x.key() == key)
+            .and_then(AuxVar::value_integer)
+    };
+    assert_eq!(find(AuxVarType::HwCap2), Some(0x2));
+    assert_eq!(find(AuxVarType::HwCap3), Some(0x1234));
+    assert_eq!(find(AuxVarType::HwCap4), Some(0x5678));
+    assert_eq!(AuxVarType::try_from(29), Ok(AuxVarType::HwCap3));
+    assert_eq!(AuxVarType::try_from(30), Ok(AuxVarType::HwCap4));
+}
+
+#[test]
+fn test_roundtrip_rseq() {
+    let buf = InitialLinuxLibcStackLayoutBuilder::new()
+        .add_aux_v(AuxVar::RseqFeatureSize(28))
+        .add_aux_v(AuxVar::RseqAlign(32))
+        .build_ref(None);
+    let parsed = InitialLinuxLibcStackLayout::from(buf.as_slice());
+    let aux = unsafe { parsed.aux_var_iter() }.collect::<Vec<_>>();
+    let find = |key| {
+        aux.iter()
+            .find(|x| x.key() == key)
+            .and_then(AuxVar::value_integer)
+    };
+    assert_eq!(find(AuxVarType::RseqFeatureSize), Some(28));
+    assert_eq!(find(AuxVarType::RseqAlign), Some(32));
+}
@@ -0,0 +1,163 @@
+/*
+MIT License
+
+Copyright (c) 2021 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`LayoutSnapshot`].
+
+use crate::{AuxVar, AuxVarType};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+
+/// Owned copy of a parsed data structure, which is independent of the lifetime of the
+/// underlying buffer.
+///
+/// All strings and referenced bytes are copied and pointers are stored as `u64`. Created by
+/// [`crate::InitialLinuxLibcStackLayout::snapshot`]. With the `serde` feature, it can be
+/// serialized, e.g., to store captured layouts on disk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayoutSnapshot {
+    /// Number of arguments.
+    pub argc: usize,
+    /// Arguments without the terminating null byte.
+    pub argv: Vec<String>,
+    /// Environment variables without the terminating null byte.
+    pub envv: Vec<String>,
+    /// Entries of the auxiliary vector, including the terminating [`AuxVarType::Null`] entry.
+    pub auxv: Vec<(AuxVarType, SnapshotValue)>,
+}
+
+impl LayoutSnapshot {
+    /// Returns a hash of the logical content, that is independent of the address of the
+    /// data structure. Hence, two layouts that only differ in their location have the same
+    /// hash. All values are hashed except for [`SnapshotValue::Pointer`], as these are
+    /// absolute addresses (such as [`AuxVarType::Entry`]) that vary between program loads.
+    ///
+    /// The hash (FNV-1a) is stable across program runs and platforms with the same endianness
+    /// and pointer width, but it is not cryptographically secure.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = Fnv1aHasher::default();
+        self.argc.hash(&mut hasher);
+        self.argv.hash(&mut hasher);
+        self.envv.hash(&mut hasher);
+        for (key, val) in &self.auxv {
+            key.val().hash(&mut hasher);
+            if !matches!(val, SnapshotValue::Pointer(_)) {
+                val.hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+}
+
+/// Owned value of an entry in [`LayoutSnapshot::auxv`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SnapshotValue {
+    /// Integer, boolean, or flags value.
+    Integer(u64),
+    /// Pointer that doesn't point into the aux data area.
+    Pointer(u64),
+    /// C-string without the terminating null byte.
+    String(String),
+    /// Bytes referenced in the aux data area, such as [`AuxVarType::Random`].
+    Bytes(Vec<u8>),
+}
+
+impl From<&AuxVar<'_>> for SnapshotValue {
+    fn from(var: &AuxVar) -> Self {
+        match var {
+            AuxVar::Platform(val) | AuxVar::BasePlatform(val) | AuxVar::ExecFn(val) => {
+                Self::String(String::from(val.strip_suffix('\0').unwrap_or(val)))
+            }
+            AuxVar::Random(bytes) => Self::Bytes(bytes.to_vec()),
+            _ => var.value_ptr().map_or_else(
+                || Self::Integer(var.value_raw() as u64),
+                |ptr| Self::Pointer(ptr as u64),
+            ),
+        }
+    }
+}
+
+/// View of a data structure, where all pointers into the data areas are offsets to a base.
+///
+/// This allows to compare data structures, that were captured at different addresses.
+/// Created by [`crate::InitialLinuxLibcStackLayout::relative_view`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RelativeLayout {
+    /// Offsets of the arguments.
+    pub argv: Vec<isize>,
+    /// Offsets of the environment variables.
+    pub envv: Vec<isize>,
+    /// Entries of the auxiliary vector, including the terminating [`AuxVarType::Null`] entry.
+    pub auxv: Vec<(AuxVarType, RelativeValue)>,
+}
+
+/// Value of an entry in [`RelativeLayout::auxv`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RelativeValue {
+    /// Raw value of an entry, whose value doesn't live in the aux data area. This includes
+    /// pointers that don't point into the data structure, such as [`AuxVarType::Entry`].
+    Immediate(usize),
+    /// Offset of the referenced data in the aux data area to the assumed base.
+    Offset(isize),
+}
+
+/// Owned environment variable, split at the first `=`. Created by
+/// [`crate::InitialLinuxLibcStackLayout::env_entries`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnvEntry {
+    /// Part before the first `=`, or the whole entry, if it has no `=`.
+    pub key: String,
+    /// Part after the first `=`. Empty, if the entry has no `=`.
+    pub value: String,
+}
+
+/// Simple implementation of the 64-bit FNV-1a hash function. In contrast to the hasher of the
+/// standard library, it is available in `no_std` environments and doesn't use a random seed.
+#[derive(Debug)]
+struct Fnv1aHasher(u64);
+
+impl Default for Fnv1aHasher {
+    fn default() -> Self {
+        // FNV offset basis
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1aHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            // FNV prime
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+/*
+MIT License
+
+Copyright (c) 2021 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Test data for the unit tests: captures of real initial stack layouts created by Linux.
+
+use std::vec::Vec;
+
+/// Wrapper that guarantees the alignment that the parser expects.
+#[repr(C, align(16))]
+pub(crate) struct Aligned<const N: usize>(pub [u8; N]);
+
+/// Address of `argc` in the address space of the process, that [`TEST_DATA_X86_64`] was
+/// captured from.
+pub(crate) const TEST_DATA_X86_64_BASE: usize = 0x7fff_ffff_edc0;
+
+/// Initial stack layout of a static x86_64 binary, captured with Linux 6.18 from `argc` up
+/// to the end of the stack. The process was started with ASLR disabled like this:
+/// `env -i HOME=/root TERM=xterm-256color PATH=/bin:/usr/bin setarch x86_64 -R ./cap first second third`.
+///
+/// The pointers are only valid in the address space of the original process (see
+/// [`TEST_DATA_X86_64_BASE`] and [`test_data_x86_64_relocated`]).
+pub(crate) static TEST_DATA_X86_64: Aligned<576> = Aligned([
+    0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xa7, 0xef, 0xff, 0xff, 0xff, 0x7f, 0x00, 0x00,
+    0xad, 0xef, 0xff, 0xff, 0xff, 0x7f, 0x00, 0x00, 0xb3, 0xef, 0xff, 0xff, 0xff, 0x7f, 0x00, 0x00,
+    0xba, 0xef, 0xff, 0xff, 0xff, 0x7f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xc0, 0xef, 0xff, 0xff, 0xff, 0x7f, 0x00, 0x00, 0xcb, 0xef, 0xff, 0xff, 0xff, 0x7f, 0x00, 0x00,
+    0xdf, 0xef, 0xff, 0xff, 0xff, 0x7f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x21, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xd0, 0xff, 0xf7, 0xff, 0x7f, 0x00, 0x00,
+    0x33, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xb0, 0x2e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xfb, 0x8b, 0x0f, 0x00, 0x00, 0x00, 0x00,
+    0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x64, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x38, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x60, 0x15, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x0d, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x0e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x17, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x19, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x89, 0xef, 0xff, 0xff, 0xff, 0x7f, 0x00, 0x00,
+    0x1a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x1f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf2, 0xef, 0xff, 0xff, 0xff, 0x7f, 0x00, 0x00,
+    0x0f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x99, 0xef, 0xff, 0xff, 0xff, 0x7f, 0x00, 0x00,
+    0x1b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x1c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x25, 0x2e, 0xd6, 0x80, 0x47, 0x96, 0x4d,
+    0x03, 0x09, 0x12, 0x71, 0x7e, 0xcb, 0x6f, 0x87, 0x25, 0x78, 0x38, 0x36, 0x5f, 0x36, 0x34, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2e, 0x2f, 0x63, 0x61, 0x70, 0x00, 0x66, 0x69, 0x72,
+    0x73, 0x74, 0x00, 0x73, 0x65, 0x63, 0x6f, 0x6e, 0x64, 0x00, 0x74, 0x68, 0x69, 0x72, 0x64, 0x00,
+    0x48, 0x4f, 0x4d, 0x45, 0x3d, 0x2f, 0x72, 0x6f, 0x6f, 0x74, 0x00, 0x54, 0x45, 0x52, 0x4d, 0x3d,
+    0x78, 0x74, 0x65, 0x72, 0x6d, 0x2d, 0x32, 0x35, 0x36, 0x63, 0x6f, 0x6c, 0x6f, 0x72, 0x00, 0x50,
+    0x41, 0x54, 0x48, 0x3d, 0x2f, 0x62, 0x69, 0x6e, 0x3a, 0x2f, 0x75, 0x73, 0x72, 0x2f, 0x62, 0x69,
+    0x6e, 0x00, 0x2e, 0x2f, 0x63, 0x61, 0x70, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+]);
+
+/// Returns a copy of [`TEST_DATA_X86_64`], where all pointers that reference data inside the
+/// captured structure are relocated into the returned buffer. Hence, the data can be
+/// dereferenced by the parser.
+pub(crate) fn test_data_x86_64_relocated() -> Vec<u8> {
+    let mut buf = TEST_DATA_X86_64.0.to_vec();
+    let new_base = buf.as_ptr() as usize;
+    let captured_range = TEST_DATA_X86_64_BASE..TEST_DATA_X86_64_BASE + buf.len();
+
+    let read = |buf: &[u8], offset: usize| {
+        usize::from_ne_bytes(buf[offset..offset + 8].try_into().unwrap())
+    };
+    let mut relocate = |offset: usize| {
+        let val = read(&buf, offset);
+        if captured_range.contains(&val) {
+            let val = val - TEST_DATA_X86_64_BASE + new_base;
+            buf[offset..offset + 8].copy_from_slice(&val.to_ne_bytes());
+        }
+    };
+
+    // argv and envv: two null-terminated arrays after argc
+    let mut offset = 8;
+    for _ in 0..2 {
+        while read(&TEST_DATA_X86_64.0, offset) != 0 {
+            relocate(offset);
+            offset += 8;
+        }
+        offset += 8;
+    }
+    // auxv: (key, value)-pairs until AT_NULL
+    while read(&TEST_DATA_X86_64.0, offset) != 0 {
+        relocate(offset + 8);
+        offset += 16;
+    }
+
+    buf
+}